@@ -21,16 +21,45 @@ pub use tcp::{State as TcpState, TcpSocket};
 #[cfg(feature = "socket-udp")]
 pub use udp::{State as UdpState, UdpSocket};
 
-pub use self::set::{Handle as SocketHandle, Set as SocketSet};
+pub use self::set::{GenerationalHandle, Handle as SocketHandle, Set as SocketSet};
 
 pub use self::ref_::Ref as SocketRef;
 
+// The canonical address types of the crate. Everything, including downstream
+// code, should use these re-exports rather than the underlying address crate.
+pub use no_std_net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use embassy_time::{Duration, Instant};
+
+/// The default interval between checks for available data on a socket.
+pub const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The default time a socket closed by the remote is kept, before it is recycled.
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The default maximum size of a UDP datagram, matching the per-write limit
+/// of common u-blox modules.
+pub const DEFAULT_MAX_DATAGRAM_SIZE: usize = 1024;
+
+/// The maximum amount of multicast groups a UDP socket can be a member of,
+/// see [UdpSocket::join_multicast_group].
+pub const MAX_MULTICAST_GROUPS: usize = 4;
+
+/// The highest security profile id supported by u-blox modules, see
+/// [TcpSocket::set_security_profile].
+pub const MAX_SECURITY_PROFILE: u8 = 4;
+
 /// The error type for the networking stack.
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error {
     /// An operation cannot proceed because a buffer is empty or full.
+    ///
+    /// This is returned by all checked enqueue and dequeue operations, e.g.
+    /// when enqueueing into a full receive buffer. Unchecked operations such as
+    /// `rx_enqueue_slice` truncate instead, and report the amount of octets
+    /// actually enqueued.
     Exhausted,
     /// An operation is not permitted in the current state.
     Illegal,
@@ -54,6 +83,49 @@ pub enum Error {
 
 type Result<T> = core::result::Result<T, Error>;
 
+/// Receive buffer thresholds for receive-side flow control.
+///
+/// Once a receive buffer fills above `high`, the driver should stop reading
+/// from the modem, until it has been drained below `low` again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Watermarks {
+    pub low: usize,
+    pub high: usize,
+}
+
+impl Watermarks {
+    /// Return the default watermarks for a buffer of `capacity` octets, at a
+    /// quarter and three quarters of the capacity.
+    pub const fn for_capacity(capacity: usize) -> Self {
+        Self {
+            low: capacity / 4,
+            high: capacity * 3 / 4,
+        }
+    }
+}
+
+/// The reason a socket needs attention from the driver, see
+/// [SocketSet::next_deadline].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PollReason {
+    /// The available data should be updated from the modem, see
+    /// [Socket::should_update_available_data].
+    DataPoll,
+    /// The socket was closed by the remote, and should be recycled, see
+    /// [Socket::recycle].
+    RecycleDue,
+    /// A connection attempt has timed out.
+    ///
+    /// Sockets do not track connection deadlines yet, so this is never
+    /// reported.
+    ConnectTimeout,
+    /// A keep-alive probe should be sent, see [TcpSocket::needs_keepalive].
+    Keepalive,
+}
+
 /// A network socket.
 ///
 /// This enumeration abstracts the various types of sockets based on the IP protocol.
@@ -97,10 +169,101 @@ impl<const L: usize> Socket<L> {
         }
     }
 
+    /// Return whether the socket is reserved, and its creation on the modem
+    /// is pending confirmation, see [SocketSet::reserve].
+    pub fn is_pending(&self) -> bool {
+        self.meta().pending_since.is_some()
+    }
+
+    pub(crate) fn meta_mut(&mut self) -> &mut SocketMeta {
+        match self {
+            #[cfg(feature = "socket-udp")]
+            Socket::Udp(ref mut socket) => &mut socket.meta,
+            #[cfg(feature = "socket-tcp")]
+            Socket::Tcp(ref mut socket) => &mut socket.meta,
+        }
+    }
+
+    /// Latch an error reported by the modem for this socket, see
+    /// [TcpSocket::set_last_error].
+    pub fn set_last_error(&mut self, error: Error) {
+        match self {
+            Socket::Tcp(s) => s.set_last_error(error),
+            Socket::Udp(s) => s.set_last_error(error),
+        }
+    }
+
+    /// Same as [set_last_error](#method.set_last_error), but with `now` as the
+    /// time the error occurred.
+    pub fn set_last_error_at(&mut self, error: Error, now: Instant) {
+        match self {
+            Socket::Tcp(s) => s.set_last_error_at(error, now),
+            Socket::Udp(s) => s.set_last_error_at(error, now),
+        }
+    }
+
+    /// Return the time the last error was latched, if any, see
+    /// [TcpSocket::last_error_at].
+    pub fn last_error_at(&self) -> Option<Instant> {
+        match self {
+            Socket::Tcp(s) => s.last_error_at(),
+            Socket::Udp(s) => s.last_error_at(),
+        }
+    }
+
+    /// Return and clear the latched error, if any.
+    pub fn take_last_error(&mut self) -> Option<Error> {
+        match self {
+            Socket::Tcp(s) => s.take_last_error(),
+            Socket::Udp(s) => s.take_last_error(),
+        }
+    }
+
+    /// Return whether an error is latched on this socket.
+    pub fn has_error(&self) -> bool {
+        match self {
+            Socket::Tcp(s) => s.has_error(),
+            Socket::Udp(s) => s.has_error(),
+        }
+    }
+
+    /// Return the remote endpoint of the socket, if any.
+    pub fn endpoint(&self) -> Option<SocketAddr> {
+        match self {
+            Socket::Tcp(s) => s.endpoint(),
+            Socket::Udp(s) => s.endpoint(),
+        }
+    }
+
     pub fn get_type(&self) -> SocketType {
+        self.meta().ty
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            Socket::Tcp(s) => s.reset(),
+            Socket::Udp(s) => s.reset(),
+        }
+    }
+
+    pub fn reset_at(&mut self, now: Instant) {
         match self {
-            Socket::Tcp(_) => SocketType::Tcp,
-            Socket::Udp(_) => SocketType::Udp,
+            Socket::Tcp(s) => s.reset_at(now),
+            Socket::Udp(s) => s.reset(),
+        }
+    }
+
+    pub fn reset_timers(&mut self) {
+        match self {
+            Socket::Tcp(s) => s.reset_timers(),
+            Socket::Udp(s) => s.reset_timers(),
+        }
+    }
+
+    pub fn reset_timers_at(&mut self, now: Instant) {
+        match self {
+            Socket::Tcp(s) => s.reset_timers_at(now),
+            Socket::Udp(s) => s.reset_timers_at(now),
         }
     }
 
@@ -111,6 +274,13 @@ impl<const L: usize> Socket<L> {
         }
     }
 
+    pub fn should_update_available_data_at(&mut self, now: Instant) -> bool {
+        match self {
+            Socket::Tcp(s) => s.should_update_available_data_at(now),
+            Socket::Udp(s) => s.should_update_available_data_at(now),
+        }
+    }
+
     pub fn available_data(&self) -> usize {
         match self {
             Socket::Tcp(s) => s.get_available_data(),
@@ -125,6 +295,22 @@ impl<const L: usize> Socket<L> {
         }
     }
 
+    pub fn recycle_at(&self, now: Instant) -> bool {
+        match self {
+            Socket::Tcp(s) => s.recycle_at(now),
+            Socket::Udp(s) => s.recycle_at(now),
+        }
+    }
+
+    /// Return the earliest time this socket needs attention, and why, with
+    /// `now` as the current time.
+    pub fn next_deadline_at(&self, now: Instant) -> Option<(Instant, PollReason)> {
+        match self {
+            Socket::Tcp(s) => s.next_deadline_at(now),
+            Socket::Udp(s) => s.next_deadline_at(now),
+        }
+    }
+
     pub fn closed_by_remote(&mut self) {
         match self {
             Socket::Tcp(s) => s.closed_by_remote(),
@@ -132,6 +318,13 @@ impl<const L: usize> Socket<L> {
         }
     }
 
+    pub fn closed_by_remote_at(&mut self, now: Instant) {
+        match self {
+            Socket::Tcp(s) => s.closed_by_remote_at(now),
+            Socket::Udp(s) => s.closed_by_remote_at(now),
+        }
+    }
+
     pub fn set_available_data(&mut self, available_data: usize) {
         match self {
             Socket::Tcp(s) => s.set_available_data(available_data),
@@ -139,6 +332,20 @@ impl<const L: usize> Socket<L> {
         }
     }
 
+    pub fn mark_data_available(&mut self, available_data: usize) {
+        match self {
+            Socket::Tcp(s) => s.mark_data_available(available_data),
+            Socket::Udp(s) => s.mark_data_available(available_data),
+        }
+    }
+
+    pub fn consume_available(&mut self, n: usize) {
+        match self {
+            Socket::Tcp(s) => s.consume_available(n),
+            Socket::Udp(s) => s.consume_available(n),
+        }
+    }
+
     pub fn rx_enqueue_slice(&mut self, data: &[u8]) -> usize {
         match self {
             Socket::Tcp(s) => s.rx_enqueue_slice(data),
@@ -146,6 +353,22 @@ impl<const L: usize> Socket<L> {
         }
     }
 
+    pub fn try_rx_enqueue_slice(&mut self, data: &[u8]) -> Result<()> {
+        match self {
+            Socket::Tcp(s) => s.try_rx_enqueue_slice(data),
+            Socket::Udp(s) => s.try_rx_enqueue_slice(data),
+        }
+    }
+
+    /// Return the octets in the receive buffer in read order, as two slices,
+    /// without removing them, see [RingBuffer::as_slices].
+    pub fn rx_slices(&self) -> (&[u8], &[u8]) {
+        match self {
+            Socket::Tcp(s) => s.rx_buffer().as_slices(),
+            Socket::Udp(s) => s.rx_buffer().as_slices(),
+        }
+    }
+
     pub fn rx_window(&self) -> usize {
         match self {
             Socket::Tcp(s) => s.rx_window(),
@@ -153,12 +376,50 @@ impl<const L: usize> Socket<L> {
         }
     }
 
+    pub fn set_rx_cap(&mut self, cap: usize) {
+        match self {
+            Socket::Tcp(s) => s.set_rx_cap(cap),
+            Socket::Udp(s) => s.set_rx_cap(cap),
+        }
+    }
+
     pub fn can_recv(&self) -> bool {
         match self {
             Socket::Tcp(s) => s.can_recv(),
             Socket::Udp(s) => s.can_recv(),
         }
     }
+
+    /// Return the capacity of the socket buffer, in octets.
+    pub fn buffer_capacity(&self) -> usize {
+        L
+    }
+
+    /// Return the amount of octets queued in the socket buffer.
+    pub fn buffer_used(&self) -> usize {
+        match self {
+            Socket::Tcp(s) => s.recv_queue(),
+            Socket::Udp(s) => s.recv_queue(),
+        }
+    }
+
+    /// Return whether the receive buffer is above its high watermark, and
+    /// the driver should stop reading from the modem.
+    pub fn should_throttle(&self) -> bool {
+        match self {
+            Socket::Tcp(s) => s.should_throttle(),
+            Socket::Udp(s) => s.should_throttle(),
+        }
+    }
+
+    /// Return whether the receive buffer is below its low watermark, and
+    /// the driver may resume reading from the modem.
+    pub fn should_resume(&self) -> bool {
+        match self {
+            Socket::Tcp(s) => s.should_resume(),
+            Socket::Udp(s) => s.should_resume(),
+        }
+    }
 }
 
 /// A conversion trait for network sockets.
@@ -1,14 +1,23 @@
-use super::SocketHandle;
+use super::{SocketHandle, SocketType};
+use embassy_time::Instant;
 
 /// Network socket metadata.
 ///
 /// This includes things that only external (to the socket, that is) code
 /// is interested in, but which are more conveniently stored inside the socket itself.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Meta {
     /// Handle of this socket within its enclosing `SocketSet`.
     /// Mainly useful for debug output.
     pub(crate) handle: SocketHandle,
+    /// Type of this socket, set at construction.
+    pub(crate) ty: SocketType,
+    /// Time the socket was reserved, while its creation on the modem is
+    /// pending confirmation.
+    pub(crate) pending_since: Option<Instant>,
+    /// Generation this socket was added to its enclosing `SocketSet` in, see
+    /// `GenerationalHandle`.
+    pub(crate) generation: u16,
 }
 
 impl Meta {
@@ -3,6 +3,11 @@ use core::ops::{Deref, DerefMut};
 /// A smart pointer to a socket.
 ///
 /// Allows the network stack to efficiently determine if the socket state was changed in any way.
+///
+/// `Ref` implements [Deref] and [DerefMut] to the socket, so socket methods can be
+/// called directly through it, e.g. `socket_set.get::<TcpSocket<L>>(handle)?.recv_slice(buf)`.
+/// Like other smart pointers, its own functions are associated functions, such as
+/// [Ref::get_mut], to avoid shadowing methods of the socket.
 pub struct Ref<'a, T: 'a> {
     socket: &'a mut T,
     consumed: bool,
@@ -35,6 +40,21 @@ impl<'a, T: 'a> Ref<'a, T> {
         ref_.consumed = true;
         ref_.socket
     }
+
+    /// Return a mutable reference to the socket, for passing on to code
+    /// expecting a `&mut T`.
+    pub fn get_mut(ref_: &mut Self) -> &mut T {
+        ref_.socket
+    }
+
+    /// Map a smart pointer to a socket into a smart pointer to a part of it,
+    /// e.g. to downcast it.
+    pub fn map<U, F>(ref_: Self, f: F) -> Ref<'a, U>
+    where
+        F: FnOnce(&'a mut T) -> &'a mut U,
+    {
+        Ref::new(f(Self::into_inner(ref_)))
+    }
 }
 
 impl<'a, T> Deref for Ref<'a, T> {
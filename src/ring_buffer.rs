@@ -13,20 +13,32 @@ use heapless::Vec;
 ///
 /// This implementation is suitable for both simple uses such as a FIFO queue
 /// of UDP packets, and advanced ones such as a TCP reassembly buffer.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct RingBuffer<T, const N: usize> {
-    storage: Vec<T, N>,
+    storage: [T; N],
     read_at: usize,
     length: usize,
 }
 
+impl<T: Default + Clone, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: Default + Clone, const N: usize> RingBuffer<T, N> {
     /// Create a ring buffer with the given storage.
     ///
     /// During creation, every element in `storage` is reset.
     pub fn new() -> RingBuffer<T, N> {
-        let mut storage = Vec::new();
-        storage.resize_default(N).ok();
+        RingBuffer::from_storage(core::array::from_fn(|_| T::default()))
+    }
+
+    /// Create an empty ring buffer using `storage`, e.g. `[0; N]`.
+    ///
+    /// Unlike [new](#method.new), this is a `const fn`, and can be used to
+    /// initialize a ring buffer in a `static`.
+    pub const fn from_storage(storage: [T; N]) -> RingBuffer<T, N> {
         RingBuffer {
             storage,
             read_at: 0,
@@ -51,9 +63,19 @@ impl<T: Default + Clone, const N: usize> RingBuffer<T, N> {
         self.length = 0;
     }
 
+    /// Shorten the ring buffer to `new_len` elements, discarding the most
+    /// recently enqueued elements.
+    ///
+    /// If `new_len` is greater than the current length, this has no effect.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.length {
+            self.length = new_len;
+        }
+    }
+
     /// Return the maximum number of elements in the ring buffer.
     pub fn capacity(&self) -> usize {
-        self.storage.capacity()
+        N
     }
 
     /// Return the current number of elements in the ring buffer.
@@ -152,6 +174,29 @@ impl<T: Default + Clone, const N: usize> RingBuffer<T, N> {
         }
     }
 
+    /// Enqueue elements from `iter` into the buffer until it is full or `iter`
+    /// is exhausted, and return the amount of elements enqueued.
+    ///
+    /// No element is taken from `iter` once the buffer is full.
+    pub fn extend_from_iter<I>(&mut self, mut iter: I) -> usize
+    where
+        I: Iterator<Item = T>,
+    {
+        let mut count = 0;
+        while !self.is_full() {
+            match iter.next() {
+                Some(element) => {
+                    let index = self.get_idx_unchecked(self.length);
+                    self.storage[index] = element;
+                    self.length += 1;
+                    count += 1;
+                }
+                None => break,
+            }
+        }
+        count
+    }
+
     /// Dequeue an element from the buffer, and return a reference to it,
     /// or return `Err(Error::Exhausted)` if the buffer is empty.
     ///
@@ -220,6 +265,70 @@ impl<T: Default + core::fmt::Debug + Clone, const N: usize> RingBuffer<T, N> {
         size_1 + size_2
     }
 
+    /// Same as [enqueue_slice](#method.enqueue_slice), but also return the
+    /// free space left after enqueueing, as `(enqueued, window)`.
+    pub fn enqueue_slice_with_remaining(&mut self, data: &[T]) -> (usize, usize)
+    where
+        T: Copy,
+    {
+        let size = self.enqueue_slice(data);
+        (size, self.window())
+    }
+
+    /// Enqueue all of the given slice into the buffer, or nothing, and return
+    /// `Err(Error::Exhausted)` if it does not fit.
+    ///
+    /// Unlike [enqueue_slice](#method.enqueue_slice), a message is never split
+    /// at the point the buffer runs full.
+    pub fn enqueue_slice_atomic(&mut self, data: &[T]) -> Result<()>
+    where
+        T: Copy,
+    {
+        if data.len() > self.window() {
+            return Err(Error::Exhausted);
+        }
+
+        self.enqueue_slice(data);
+        Ok(())
+    }
+
+    /// Enqueue up to `count` copies of `element` into the buffer, and return the
+    /// amount of elements that could fit.
+    pub fn enqueue_fill(&mut self, element: T, count: usize) -> usize {
+        let (size_1, ()) = self.enqueue_many_with(|buf| {
+            let size = cmp::min(buf.len(), count);
+            buf[..size].fill(element.clone());
+            (size, ())
+        });
+        let (size_2, ()) = self.enqueue_many_with(|buf| {
+            let size = cmp::min(buf.len(), count - size_1);
+            buf[..size].fill(element);
+            (size, ())
+        });
+        size_1 + size_2
+    }
+
+    /// Move up to `max` elements from `other` into the buffer, without an
+    /// intermediate buffer, and return the amount of elements moved.
+    pub fn enqueue_from<const M: usize>(
+        &mut self,
+        other: &mut RingBuffer<T, M>,
+        max: usize,
+    ) -> usize
+    where
+        T: Copy,
+    {
+        let (size_1, ()) = other.dequeue_many_with(|buf| {
+            let size = cmp::min(buf.len(), max);
+            (self.enqueue_slice(&buf[..size]), ())
+        });
+        let (size_2, ()) = other.dequeue_many_with(|buf| {
+            let size = cmp::min(buf.len(), max - size_1);
+            (self.enqueue_slice(&buf[..size]), ())
+        });
+        size_1 + size_2
+    }
+
     /// Call `f` with the largest contiguous slice of allocated buffer elements,
     /// and dequeue the amount of elements returned by `f`.
     ///
@@ -358,6 +467,47 @@ impl<T: Default + Clone, const N: usize> RingBuffer<T, N> {
         self.length += count;
     }
 
+    /// Return a raw pointer to, and the length of, the largest contiguous region
+    /// of unallocated buffer elements, or `None` if the buffer is full.
+    ///
+    /// This is intended for arming a DMA transfer into the buffer, where the
+    /// borrow of a slice from [get_unallocated] cannot span the transfer.
+    ///
+    /// # Safety contract
+    /// The returned pointer is valid for writes of up to the returned length,
+    /// until the buffer is next accessed. The caller must not write past the
+    /// returned length, and must call [dma_commit] with the amount of elements
+    /// written once the transfer is complete, before otherwise accessing the
+    /// buffer.
+    ///
+    /// [get_unallocated]: #method.get_unallocated
+    /// [dma_commit]: #method.dma_commit
+    pub fn dma_write_region(&mut self) -> Option<(*mut T, usize)> {
+        if self.length == 0 {
+            // Ring is currently empty. Reset `read_at` to optimize
+            // for contiguous space.
+            self.read_at = 0;
+        }
+
+        let size = self.contiguous_window();
+        if size == 0 {
+            return None;
+        }
+
+        let write_at = self.get_idx(self.length);
+        Some((self.storage[write_at..].as_mut_ptr(), size))
+    }
+
+    /// Enqueue the given number of elements written through the region
+    /// returned by [dma_write_region](#method.dma_write_region).
+    ///
+    /// # Panics
+    /// Panics if the number of elements given exceeds the length of the region.
+    pub fn dma_commit(&mut self, count: usize) {
+        assert!(count <= self.contiguous_window());
+        self.length += count;
+    }
+
     /// Return the largest contiguous slice of allocated buffer elements starting
     /// at the given offset past the first allocated element, and up to the given size.
     pub fn get_allocated(&self, offset: usize, mut size: usize) -> &[T] {
@@ -380,6 +530,90 @@ impl<T: Default + Clone, const N: usize> RingBuffer<T, N> {
         &self.storage[start_at..start_at + size]
     }
 
+    /// Dequeue up to `count` allocated elements without copying them out, and
+    /// return the amount of elements discarded.
+    pub fn discard(&mut self, count: usize) -> usize {
+        let count = count.min(self.len());
+        self.dequeue_allocated(count);
+        count
+    }
+
+    /// Return the first allocated element without dequeueing it, or `None` if
+    /// the buffer is empty.
+    pub fn peek_byte(&self) -> Option<T>
+    where
+        T: Copy,
+    {
+        self.peek_byte_at(0)
+    }
+
+    /// Return the allocated element at the given offset past the first
+    /// allocated element without dequeueing it, or `None` if the offset is
+    /// past the end of the allocated elements.
+    pub fn peek_byte_at(&self, offset: usize) -> Option<T>
+    where
+        T: Copy,
+    {
+        if offset >= self.length {
+            return None;
+        }
+        Some(self.storage[self.get_idx_unchecked(offset)])
+    }
+
+    /// Return all allocated elements in read order, as two slices.
+    ///
+    /// The second slice is empty unless the allocated elements wrap around the
+    /// end of the storage.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let first = self.get_allocated(0, self.len());
+        let second = self.get_allocated(first.len(), self.len() - first.len());
+        (first, second)
+    }
+
+    /// Iterate all allocated elements in read order, as mutable, e.g. to
+    /// transform them in place before they are dequeued.
+    ///
+    /// Only the contents of the elements can be changed, the buffer keeps its
+    /// length and read position.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        let read_at = self.read_at;
+        let first_len = cmp::min(self.length, N - read_at);
+        let second_len = self.length - first_len;
+        let (head, tail) = self.storage.split_at_mut(read_at);
+        tail[..first_len]
+            .iter_mut()
+            .chain(head[..second_len].iter_mut())
+    }
+
+    /// Return the first `n` allocated elements in read order, as two slices,
+    /// without dequeueing them, or `None` if fewer than `n` are allocated.
+    ///
+    /// This allows handing a complete message to a parser by reference, before
+    /// dequeueing it with [discard](#method.discard).
+    pub fn read_prefix(&self, n: usize) -> Option<(&[T], &[T])> {
+        if n > self.len() {
+            return None;
+        }
+
+        let first = self.get_allocated(0, n);
+        let second = self.get_allocated(first.len(), n - first.len());
+        Some((first, second))
+    }
+
+    /// Copy as many allocated elements as fit into `data`, in read order,
+    /// without dequeueing them, and return the amount copied.
+    pub fn peek_into(&self, data: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let (first, second) = self.as_slices();
+        let size_1 = cmp::min(first.len(), data.len());
+        data[..size_1].copy_from_slice(&first[..size_1]);
+        let size_2 = cmp::min(second.len(), data.len() - size_1);
+        data[size_1..size_1 + size_2].copy_from_slice(&second[..size_2]);
+        size_1 + size_2
+    }
+
     /// Read as many elements from allocated buffer elements into the given slice
     /// starting at the given offset past the first allocated element, and return
     /// the amount read.
@@ -409,6 +643,39 @@ impl<T: Default + Clone, const N: usize> RingBuffer<T, N> {
         self.length -= count;
         self.read_at = self.get_idx(count);
     }
+
+    /// Move the allocated elements to the start of the storage, and return them
+    /// as a single contiguous slice.
+    ///
+    /// This is useful when a parser cannot handle data split across the end of
+    /// the storage. It is O(n) in the capacity of the buffer, so avoid calling
+    /// it when the data is already contiguous, or on every read.
+    pub fn make_contiguous(&mut self) -> &[T] {
+        self.storage.rotate_left(self.read_at);
+        self.read_at = 0;
+        &self.storage[..self.length]
+    }
+
+    /// Return the offset past the first allocated element of the first element
+    /// equal to `needle`, or `None` if no allocated element matches.
+    ///
+    /// The search follows read order, and continues across the point where the
+    /// allocated elements wrap around the end of the storage.
+    pub fn find(&self, needle: T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        let first = self.get_allocated(0, self.len());
+        if let Some(pos) = first.iter().position(|e| *e == needle) {
+            return Some(pos);
+        }
+
+        let second = self.get_allocated(first.len(), self.len() - first.len());
+        second
+            .iter()
+            .position(|e| *e == needle)
+            .map(|pos| first.len() + pos)
+    }
 }
 
 impl<T: Default + core::fmt::Debug + Copy, const N: usize> From<Vec<T, N>> for RingBuffer<T, N> {
@@ -507,6 +774,23 @@ mod tests {
         assert!(ring.is_empty());
     }
 
+    #[test]
+    fn test_buffer_extend_from_iter() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.extend_from_iter(b"....ab".iter().copied()), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+
+        let mut iter = b"cdefg".iter().copied();
+        assert_eq!(ring.extend_from_iter(&mut iter), 4);
+        assert_eq!(iter.next(), Some(b'g'));
+        assert_eq!(ring.extend_from_iter(iter), 0);
+
+        let mut data = [0; 6];
+        assert_eq!(ring.dequeue_slice(&mut data), 6);
+        assert_eq!(&data, b"abcdef");
+        assert_eq!(ring.extend_from_iter(core::iter::empty()), 0);
+    }
+
     #[test]
     fn test_buffer_enqueue_many_with() {
         let mut ring: RingBuffer<u8, 12> = RingBuffer::from_slice(&[b'.'; 12]);
@@ -853,4 +1137,253 @@ mod tests {
         let large = ring.enqueue_many(8);
         assert_eq!(large.len(), 8);
     }
+
+    #[test]
+    fn test_buffer_find() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.find(b'a'), None);
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.enqueue_slice(b"cd"), 2);
+
+        // Allocated elements are "ab" at the end of storage and "cd" at the start
+        assert_eq!(ring.find(b'a'), Some(0));
+        assert_eq!(ring.find(b'b'), Some(1));
+        assert_eq!(ring.find(b'c'), Some(2));
+        assert_eq!(ring.find(b'd'), Some(3));
+        assert_eq!(ring.find(b'.'), None);
+        assert_eq!(ring.find(b'x'), None);
+    }
+
+    #[test]
+    fn test_buffer_truncate() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.enqueue_slice(b"cd"), 2);
+
+        ring.truncate(8);
+        assert_eq!(ring.len(), 4);
+
+        // Discard across the wrap point
+        ring.truncate(1);
+        assert_eq!(ring.len(), 1);
+        assert_eq!(ring.enqueue_slice(b"xyz"), 3);
+
+        let mut data = [0; 4];
+        assert_eq!(ring.dequeue_slice(&mut data), 4);
+        assert_eq!(&data, b"axyz");
+
+        ring.truncate(0);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_buffer_enqueue_fill() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+
+        assert_eq!(ring.enqueue_fill(b'x', 3), 3);
+        assert_eq!(ring.enqueue_fill(b'y', 3), 1);
+        assert_eq!(ring.enqueue_fill(b'z', 3), 0);
+
+        let mut data = [0; 6];
+        assert_eq!(ring.dequeue_slice(&mut data), 6);
+        assert_eq!(&data, b"abxxxy");
+    }
+
+    #[test]
+    fn test_buffer_enqueue_slice_with_remaining() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.enqueue_slice_with_remaining(b"abcd"), (4, 2));
+        assert_eq!(ring.enqueue_slice_with_remaining(b"efgh"), (2, 0));
+        assert_eq!(ring.enqueue_slice_with_remaining(b"ij"), (0, 0));
+    }
+
+    #[test]
+    fn test_buffer_enqueue_slice_atomic() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+
+        assert_eq!(ring.enqueue_slice_atomic(b"cdefg"), Err(Error::Exhausted));
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.enqueue_slice_atomic(b"cdef"), Ok(()));
+        assert_eq!(ring.enqueue_slice_atomic(b""), Ok(()));
+        assert_eq!(ring.enqueue_slice_atomic(b"g"), Err(Error::Exhausted));
+
+        let mut data = [0; 6];
+        assert_eq!(ring.dequeue_slice(&mut data), 6);
+        assert_eq!(&data, b"abcdef");
+    }
+
+    #[test]
+    fn test_buffer_enqueue_from() {
+        let mut src: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(src.enqueue_slice(b"....ab"), 6);
+        assert_eq!(src.dequeue_many(4), b"....");
+        assert_eq!(src.enqueue_slice(b"cdef"), 4);
+
+        let mut dst: RingBuffer<u8, 4> = RingBuffer::new();
+        assert_eq!(dst.enqueue_from(&mut src, 3), 3);
+        assert_eq!(src.len(), 3);
+
+        // Limited by the free space in `dst`
+        assert_eq!(dst.enqueue_from(&mut src, 3), 1);
+        assert_eq!(src.len(), 2);
+
+        let mut data = [0; 4];
+        assert_eq!(dst.dequeue_slice(&mut data), 4);
+        assert_eq!(&data, b"abcd");
+
+        assert_eq!(dst.enqueue_from(&mut src, 8), 2);
+        assert!(src.is_empty());
+        assert_eq!(dst.dequeue_slice(&mut data), 2);
+        assert_eq!(&data[..2], b"ef");
+    }
+
+    #[test]
+    fn test_buffer_dma_write_region() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dma_write_region(), None);
+        assert_eq!(ring.dequeue_many(4), b"....");
+
+        let (ptr, len) = ring.dma_write_region().unwrap();
+        assert_eq!(len, 4);
+        unsafe { core::ptr::copy_nonoverlapping(b"cde".as_ptr(), ptr, 3) };
+        ring.dma_commit(3);
+
+        let mut data = [0; 6];
+        assert_eq!(ring.dequeue_slice(&mut data), 5);
+        assert_eq!(&data[..5], b"abcde");
+    }
+
+    #[test]
+    fn test_buffer_discard() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.discard(3), 0);
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.enqueue_slice(b"cdef"), 4);
+
+        // Discard across the wrap point
+        assert_eq!(ring.discard(3), 3);
+        assert_eq!(ring.peek_byte(), Some(b'd'));
+
+        assert_eq!(ring.discard(8), 3);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_buffer_as_slices() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.as_slices(), (&b""[..], &b""[..]));
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.as_slices(), (&b"ab"[..], &b""[..]));
+
+        assert_eq!(ring.enqueue_slice(b"cd"), 2);
+        assert_eq!(ring.as_slices(), (&b"ab"[..], &b"cd"[..]));
+    }
+
+    #[test]
+    fn test_buffer_read_prefix() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.read_prefix(0), Some((&b""[..], &b""[..])));
+        assert_eq!(ring.read_prefix(1), None);
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.enqueue_slice(b"cd"), 2);
+
+        // The prefix ends before the wrap
+        assert_eq!(ring.read_prefix(1), Some((&b"a"[..], &b""[..])));
+        // The prefix crosses the wrap
+        assert_eq!(ring.read_prefix(3), Some((&b"ab"[..], &b"c"[..])));
+        assert_eq!(ring.read_prefix(4), Some((&b"ab"[..], &b"cd"[..])));
+        assert_eq!(ring.read_prefix(5), None);
+
+        assert_eq!(ring.discard(3), 3);
+        assert_eq!(ring.read_prefix(1), Some((&b"d"[..], &b""[..])));
+    }
+
+    #[test]
+    fn test_buffer_peek_into() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        let mut data = [0; 6];
+        assert_eq!(ring.peek_into(&mut data), 0);
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.enqueue_slice(b"cd"), 2);
+
+        assert_eq!(ring.peek_into(&mut data[..1]), 1);
+        assert_eq!(&data[..1], b"a");
+        assert_eq!(ring.peek_into(&mut data[..3]), 3);
+        assert_eq!(&data[..3], b"abc");
+        assert_eq!(ring.peek_into(&mut data), 4);
+        assert_eq!(&data[..4], b"abcd");
+        assert_eq!(ring.len(), 4);
+    }
+
+    #[test]
+    fn test_buffer_iter_mut() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.iter_mut().count(), 0);
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.enqueue_slice(b"cd"), 2);
+
+        for octet in ring.iter_mut() {
+            *octet = octet.to_ascii_uppercase();
+        }
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring.as_slices(), (&b"AB"[..], &b"CD"[..]));
+    }
+
+    #[test]
+    fn test_buffer_peek_byte() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.peek_byte(), None);
+        assert_eq!(ring.peek_byte_at(0), None);
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.enqueue_slice(b"cd"), 2);
+
+        // Allocated elements are "ab" at the end of storage and "cd" at the start
+        assert_eq!(ring.peek_byte(), Some(b'a'));
+        assert_eq!(ring.peek_byte_at(1), Some(b'b'));
+        assert_eq!(ring.peek_byte_at(2), Some(b'c'));
+        assert_eq!(ring.peek_byte_at(3), Some(b'd'));
+        assert_eq!(ring.peek_byte_at(4), None);
+        assert_eq!(ring.len(), 4);
+    }
+
+    #[test]
+    fn test_buffer_make_contiguous() {
+        let mut ring: RingBuffer<u8, 6> = RingBuffer::new();
+        assert_eq!(ring.make_contiguous(), b"");
+
+        assert_eq!(ring.enqueue_slice(b"....ab"), 6);
+        assert_eq!(ring.dequeue_many(4), b"....");
+        assert_eq!(ring.enqueue_slice(b"cd"), 2);
+        assert_eq!(ring.get_allocated(0, 4), b"ab");
+
+        assert_eq!(ring.make_contiguous(), b"abcd");
+        assert_eq!(ring.get_allocated(0, 4), b"abcd");
+        assert_eq!(ring.contiguous_window(), 2);
+
+        assert_eq!(ring.enqueue_slice(b"ef"), 2);
+        let mut data = [0; 6];
+        assert_eq!(ring.dequeue_slice(&mut data), 6);
+        assert_eq!(&data, b"abcdef");
+    }
 }
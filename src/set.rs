@@ -1,4 +1,13 @@
-use super::{AnySocket, Error, Result, Socket, SocketRef, SocketType};
+use super::tcp::CloseReason;
+use super::{
+    AnySocket, Error, Ipv4Addr, Ipv6Addr, PollReason, Result, Socket, SocketAddr, SocketAddrV4,
+    SocketAddrV6, SocketRef, SocketType,
+};
+#[cfg(feature = "socket-tcp")]
+use super::{TcpSocket, TcpState};
+#[cfg(feature = "socket-udp")]
+use super::{UdpSocket, UdpState};
+use embassy_time::{Duration, Instant};
 use heapless::Vec;
 use serde::{Deserialize, Serialize};
 
@@ -6,33 +15,107 @@ use serde::{Deserialize, Serialize};
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
 )]
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Handle(pub u8);
 
+impl core::fmt::Display for Handle {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Handle {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "{=u8}", self.0)
+    }
+}
+
+/// A handle, identifying a socket in a set, along with the generation it was
+/// added in.
+///
+/// Handles are reused once a socket is removed, so a plain [Handle] kept
+/// around may end up targeting another socket. Looking up a socket through a
+/// generational handle fails instead, see [Set::get_generational].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GenerationalHandle {
+    pub handle: Handle,
+    pub generation: u16,
+}
+
 // Implement AtatLen with 1 as the ublox module only support 0-9
 impl atat::AtatLen for Handle {
     const LEN: usize = 1;
 }
 
+/// The highest socket id supported by the ublox modules, see [Set::reserve].
+const MAX_SOCKET_ID: u8 = 9;
+
+/// Snapshot socket type tags.
+const SNAPSHOT_TCP: u8 = 0;
+const SNAPSHOT_UDP: u8 = 1;
+
+/// Snapshot endpoint tags.
+const SNAPSHOT_NO_ENDPOINT: u8 = 0;
+const SNAPSHOT_V4: u8 = 4;
+const SNAPSHOT_V6: u8 = 6;
+
+/// Largest size of a single socket in a snapshot, with an IPv6 endpoint.
+const SNAPSHOT_MAX_ENTRY: usize = 3 + 1 + 16 + 2 + 4;
+
 /// An extensible set of sockets.
 #[derive(Default, Debug)]
 pub struct Set<const N: usize, const L: usize> {
     pub sockets: Vec<Option<Socket<L>>, N>,
+    /// The socket most recently returned by `next_serviceable`.
+    last_serviced: Option<Handle>,
+    /// The generation given to the next socket added to the set.
+    next_generation: u16,
 }
 
 impl<const N: usize, const L: usize> Set<N, L> {
+    /// The maximum number of sockets the set can hold, usable in const
+    /// contexts, e.g. to size a buffer of handles.
+    pub const CAPACITY: usize = N;
+
+    /// The size of the receive buffer of each socket in the set, in octets.
+    pub const BYTES_PER_SOCKET: usize = L;
+
+    /// Return the combined size of the receive buffers of a full set, in
+    /// octets, e.g. to check the RAM budget in a const assertion.
+    pub const fn total_buffer_bytes() -> usize {
+        Self::CAPACITY * Self::BYTES_PER_SOCKET
+    }
+
     /// Create a socket set using the provided storage.
     pub fn new() -> Set<N, L> {
         let mut sockets = Vec::new();
         while sockets.len() < N {
             sockets.push(None).ok();
         }
-        Set { sockets }
+        Set {
+            sockets,
+            last_serviced: None,
+            next_generation: 0,
+        }
+    }
+
+    /// Create a socket set holding `sockets`, e.g. for deterministic
+    /// initialization when all sockets are known up front.
+    ///
+    /// This function returns `Err(Error::DuplicateSocket)` if two sockets
+    /// have the same handle.
+    pub fn from_sockets(sockets: [Socket<L>; N]) -> Result<Set<N, L>> {
+        let mut set = Set::new();
+        for socket in sockets {
+            set.add(socket)?;
+        }
+        Ok(set)
     }
 
     /// Get the maximum number of sockets the set can hold
     pub fn capacity(&self) -> usize {
-        N
+        Self::CAPACITY
     }
 
     /// Get the current number of initialized sockets, the set is holding
@@ -47,14 +130,21 @@ impl<const N: usize, const L: usize> Set<N, L> {
 
     /// Get the type of a specific socket in the set.
     ///
-    /// Returned as a [`SocketType`]
+    /// Returned as a [`SocketType`]. Unlike [`get`](#method.get), this only
+    /// borrows the set immutably and does not construct a [`SocketRef`], which
+    /// makes it suitable for routing URCs before re-borrowing the socket.
     pub fn socket_type(&self, handle: Handle) -> Option<SocketType> {
-        if let Ok(index) = self.index_of(handle) {
-            if let Some(socket) = self.sockets.get(index) {
-                return socket.as_ref().map(|s| s.get_type());
-            }
-        }
-        None
+        self.iter()
+            .find(|(h, _)| *h == handle)
+            .map(|(_, s)| s.get_type())
+    }
+
+    /// Get the handle of the first socket in the set whose remote endpoint is
+    /// `endpoint`, e.g. to route a URC that refers to the remote address.
+    pub fn find_by_endpoint(&self, endpoint: SocketAddr) -> Option<Handle> {
+        self.iter()
+            .find(|(_, s)| s.endpoint() == Some(endpoint))
+            .map(|(h, _)| h)
     }
 
     /// Add a socket to the set with the reference count 1, and return its handle.
@@ -62,7 +152,7 @@ impl<const N: usize, const L: usize> Set<N, L> {
     where
         T: Into<Socket<L>>,
     {
-        let socket = socket.into();
+        let mut socket = socket.into();
         let handle = socket.handle();
 
         debug!(
@@ -76,15 +166,265 @@ impl<const N: usize, const L: usize> Set<N, L> {
             return Err(Error::DuplicateSocket);
         }
 
+        socket.meta_mut().generation = self.next_generation;
+
         self.sockets
             .iter_mut()
             .find(|s| s.is_none())
             .ok_or(Error::SocketSetFull)?
             .replace(socket);
+        self.next_generation = self.next_generation.wrapping_add(1);
+
+        // Keep the sockets ordered by handle, to guarantee the iteration order
+        self.sockets
+            .sort_unstable_by_key(|slot| slot.as_ref().map(|s| s.handle()));
 
         Ok(handle)
     }
 
+    /// Reserve a slot in the set for a socket of type `ty`, and return its
+    /// handle, before the modem has confirmed creating the socket.
+    ///
+    /// The reserved socket is pending until [confirm](#method.confirm) is
+    /// called, and can be reclaimed by [reclaim_pending](#method.reclaim_pending)
+    /// if it never is.
+    ///
+    /// The handle is the lowest free socket id supported by the module, i.e.
+    /// 0 to 9. This function returns `Err(Error::SocketSetFull)` if the set is
+    /// full, or all of those ids are taken.
+    pub fn reserve(&mut self, ty: SocketType) -> Result<Handle> {
+        self.reserve_at(ty, Instant::now())
+    }
+
+    /// Same as [reserve](#method.reserve), but with `now` as the current time.
+    pub fn reserve_at(&mut self, ty: SocketType, now: Instant) -> Result<Handle> {
+        let id = (0..=MAX_SOCKET_ID)
+            .find(|id| self.index_of(Handle(*id)).is_err())
+            .ok_or(Error::SocketSetFull)?;
+
+        let mut socket: Socket<L> = match ty {
+            SocketType::Tcp => TcpSocket::new(id).into(),
+            SocketType::Udp => UdpSocket::new(id).into(),
+        };
+        socket.meta_mut().pending_since = Some(now);
+
+        self.add(socket)
+    }
+
+    /// Confirm that the modem has created a socket reserved by
+    /// [reserve](#method.reserve).
+    ///
+    /// This function returns `Err(Error::Illegal)` if the socket is not pending.
+    pub fn confirm(&mut self, handle: Handle) -> Result<()> {
+        let index = self.index_of(handle)?;
+        let socket = self
+            .sockets
+            .get_mut(index)
+            .and_then(|slot| slot.as_mut())
+            .ok_or(Error::InvalidSocket)?;
+
+        socket
+            .meta_mut()
+            .pending_since
+            .take()
+            .map(|_| ())
+            .ok_or(Error::Illegal)
+    }
+
+    /// Remove all reserved sockets that have been pending confirmation for at
+    /// least `timeout`, and return the amount of sockets removed.
+    pub fn reclaim_pending(&mut self, timeout: Duration) -> usize {
+        self.reclaim_pending_at(timeout, Instant::now())
+    }
+
+    /// Same as [reclaim_pending](#method.reclaim_pending), but with `now` as
+    /// the current time.
+    pub fn reclaim_pending_at(&mut self, timeout: Duration, now: Instant) -> usize {
+        let mut count = 0;
+        for slot in self.sockets.iter_mut() {
+            let expired = slot
+                .as_ref()
+                .and_then(|socket| socket.meta().pending_since)
+                .and_then(|pending_since| now.checked_duration_since(pending_since))
+                .map(|dur| dur >= timeout)
+                .unwrap_or(false);
+
+            if expired {
+                slot.take();
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Write a snapshot of the handle, type, state and endpoint of every
+    /// socket in this set into `out`, and return the amount of octets written.
+    ///
+    /// Buffer contents are not included. Sockets that do not fit in `out` are
+    /// left out. Each socket is encoded as its handle, a type tag (`0` for TCP,
+    /// `1` for UDP), a state tag (the index of the state variant), and an
+    /// endpoint: a `0` tag, or a `4`/`6` tag followed by the IP address octets,
+    /// the big endian port, and for IPv6 the big endian scope id.
+    pub fn snapshot(&self, out: &mut [u8]) -> usize {
+        let mut written = 0;
+        for (handle, socket) in self.iter() {
+            let (ty, state, endpoint) = match socket {
+                Socket::Tcp(s) => {
+                    let state = match s.state() {
+                        TcpState::Created => 0,
+                        TcpState::WaitingForConnect(_) => 1,
+                        TcpState::Connected(_) => 2,
+                        TcpState::ShutdownForWrite(_) => 3,
+                    };
+                    (SNAPSHOT_TCP, state, s.endpoint())
+                }
+                Socket::Udp(s) => {
+                    let state = match s.state() {
+                        UdpState::Closed => 0,
+                        UdpState::Established => 1,
+                    };
+                    (SNAPSHOT_UDP, state, s.endpoint())
+                }
+            };
+
+            let mut entry = [0; SNAPSHOT_MAX_ENTRY];
+            entry[..3].copy_from_slice(&[handle.0, ty, state]);
+            let len = 3 + Self::encode_endpoint(endpoint, &mut entry[3..]);
+
+            match out.get_mut(written..written + len) {
+                Some(dst) => dst.copy_from_slice(&entry[..len]),
+                None => break,
+            }
+            written += len;
+        }
+        written
+    }
+
+    /// Replace all sockets in this set with those in a snapshot written by
+    /// [snapshot](#method.snapshot).
+    ///
+    /// Sockets closed by the remote are restored as closed at the current time.
+    /// The set is left unchanged if the snapshot is invalid, returning
+    /// `Err(Error::BadLength)` if it is truncated, `Err(Error::Illegal)` if it
+    /// contains an unknown tag, and `Err(Error::SocketSetFull)` or
+    /// `Err(Error::DuplicateSocket)` if it does not fit the set.
+    pub fn restore(&mut self, data: &[u8]) -> Result<()> {
+        self.restore_at(data, Instant::now())
+    }
+
+    /// Same as [restore](#method.restore), but with `now` as the current time.
+    pub fn restore_at(&mut self, data: &[u8], now: Instant) -> Result<()> {
+        // Validate the full snapshot before touching the set
+        let mut handles: Vec<Handle, N> = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let (socket, len) = Self::decode_socket(rest, now)?;
+            if handles.contains(&socket.handle()) {
+                return Err(Error::DuplicateSocket);
+            }
+            handles
+                .push(socket.handle())
+                .map_err(|_| Error::SocketSetFull)?;
+            rest = &rest[len..];
+        }
+
+        self.clear();
+
+        let mut rest = data;
+        while !rest.is_empty() {
+            let (socket, len) = Self::decode_socket(rest, now)?;
+            self.add(socket)?;
+            rest = &rest[len..];
+        }
+        Ok(())
+    }
+
+    fn encode_endpoint(endpoint: Option<SocketAddr>, out: &mut [u8]) -> usize {
+        match endpoint {
+            None => {
+                out[0] = SNAPSHOT_NO_ENDPOINT;
+                1
+            }
+            Some(SocketAddr::V4(addr)) => {
+                out[0] = SNAPSHOT_V4;
+                out[1..5].copy_from_slice(&addr.ip().octets());
+                out[5..7].copy_from_slice(&addr.port().to_be_bytes());
+                7
+            }
+            Some(SocketAddr::V6(addr)) => {
+                out[0] = SNAPSHOT_V6;
+                out[1..17].copy_from_slice(&addr.ip().octets());
+                out[17..19].copy_from_slice(&addr.port().to_be_bytes());
+                out[19..23].copy_from_slice(&addr.scope_id().to_be_bytes());
+                23
+            }
+        }
+    }
+
+    fn decode_endpoint(data: &[u8]) -> Result<(Option<SocketAddr>, usize)> {
+        match data.first() {
+            Some(&SNAPSHOT_NO_ENDPOINT) => Ok((None, 1)),
+            Some(&SNAPSHOT_V4) => {
+                let b = data.get(1..7).ok_or(Error::BadLength)?;
+                let ip = Ipv4Addr::new(b[0], b[1], b[2], b[3]);
+                let port = u16::from_be_bytes([b[4], b[5]]);
+                Ok((Some(SocketAddr::V4(SocketAddrV4::new(ip, port))), 7))
+            }
+            Some(&SNAPSHOT_V6) => {
+                let b = data.get(1..23).ok_or(Error::BadLength)?;
+                let mut segments = [0; 8];
+                for (segment, octets) in segments.iter_mut().zip(b[..16].chunks(2)) {
+                    *segment = u16::from_be_bytes([octets[0], octets[1]]);
+                }
+                let [a, b_, c, d, e, f, g, h] = segments;
+                let ip = Ipv6Addr::new(a, b_, c, d, e, f, g, h);
+                let port = u16::from_be_bytes([b[16], b[17]]);
+                let scope_id = u32::from_be_bytes([b[18], b[19], b[20], b[21]]);
+                let addr = SocketAddrV6::new(ip, port, 0, scope_id);
+                Ok((Some(SocketAddr::V6(addr)), 23))
+            }
+            Some(_) => Err(Error::Illegal),
+            None => Err(Error::BadLength),
+        }
+    }
+
+    fn decode_socket(data: &[u8], now: Instant) -> Result<(Socket<L>, usize)> {
+        let header = data.get(..3).ok_or(Error::BadLength)?;
+        let (id, ty, state) = (header[0], header[1], header[2]);
+        let (endpoint, len) = Self::decode_endpoint(&data[3..])?;
+
+        let socket = match ty {
+            SNAPSHOT_TCP => {
+                let state = match (state, endpoint) {
+                    (0, _) => TcpState::Created,
+                    (1, Some(endpoint)) => TcpState::WaitingForConnect(endpoint),
+                    (2, Some(endpoint)) => TcpState::Connected(endpoint),
+                    (3, _) => TcpState::ShutdownForWrite(now),
+                    _ => return Err(Error::Illegal),
+                };
+                let mut socket = TcpSocket::new(id);
+                socket.set_state_at(state, now);
+                socket.into()
+            }
+            SNAPSHOT_UDP => {
+                let state = match state {
+                    0 => UdpState::Closed,
+                    1 => UdpState::Established,
+                    _ => return Err(Error::Illegal),
+                };
+                let mut socket = UdpSocket::new(id);
+                socket.set_state(state);
+                if let Some(endpoint) = endpoint {
+                    socket.bind(endpoint)?;
+                }
+                socket.into()
+            }
+            _ => return Err(Error::Illegal),
+        };
+
+        Ok((socket, 3 + len))
+    }
+
     /// Get a socket from the set by its handle, as mutable.
     pub fn get<T: AnySocket<L>>(&mut self, handle: Handle) -> Result<SocketRef<T>> {
         let index = self.index_of(handle)?;
@@ -95,6 +435,95 @@ impl<const N: usize, const L: usize> Set<N, L> {
         }
     }
 
+    /// Get the generational handle of a socket in the set, to look it up
+    /// later with [get_generational](#method.get_generational).
+    pub fn generational_handle(&self, handle: Handle) -> Result<GenerationalHandle> {
+        self.iter()
+            .find(|(h, _)| *h == handle)
+            .map(|(handle, socket)| GenerationalHandle {
+                handle,
+                generation: socket.meta().generation,
+            })
+            .ok_or(Error::InvalidSocket)
+    }
+
+    /// Get a socket from the set by its generational handle, as mutable.
+    ///
+    /// This function returns `Err(Error::InvalidSocket)` if the socket has
+    /// been removed, even if its handle has since been reused by another
+    /// socket.
+    pub fn get_generational<T: AnySocket<L>>(
+        &mut self,
+        handle: GenerationalHandle,
+    ) -> Result<SocketRef<T>> {
+        let index = self.index_of(handle.handle)?;
+
+        match self.sockets.get_mut(index).ok_or(Error::InvalidSocket)? {
+            Some(socket) if socket.meta().generation == handle.generation => {
+                Ok(T::downcast(SocketRef::new(socket))?)
+            }
+            _ => Err(Error::InvalidSocket),
+        }
+    }
+
+    /// Get a socket from the set by its handle, as mutable, inserting the
+    /// socket returned by `f` first if the handle is unknown.
+    ///
+    /// This function returns `Err(Error::SocketSetFull)` if there is no room
+    /// for the socket, and `Err(Error::Illegal)` if the socket returned by `f`
+    /// has another handle.
+    pub fn get_or_insert_with<F>(
+        &mut self,
+        handle: Handle,
+        f: F,
+    ) -> Result<SocketRef<'_, Socket<L>>>
+    where
+        F: FnOnce() -> Socket<L>,
+    {
+        if self.index_of(handle).is_err() {
+            let socket = f();
+            if socket.handle() != handle {
+                return Err(Error::Illegal);
+            }
+            self.add(socket)?;
+        }
+
+        let index = self.index_of(handle)?;
+        self.sockets[index]
+            .as_mut()
+            .map(SocketRef::new)
+            .ok_or(Error::InvalidSocket)
+    }
+
+    /// Get a TCP socket from the set by its handle, as mutable.
+    ///
+    /// Returns `Err(Error::Illegal)` if the socket is not a TCP socket.
+    #[cfg(feature = "socket-tcp")]
+    pub fn get_tcp(&mut self, handle: Handle) -> Result<SocketRef<TcpSocket<L>>> {
+        self.get(handle)
+    }
+
+    /// Get a UDP socket from the set by its handle, as mutable.
+    ///
+    /// Returns `Err(Error::Illegal)` if the socket is not a UDP socket.
+    #[cfg(feature = "socket-udp")]
+    pub fn get_udp(&mut self, handle: Handle) -> Result<SocketRef<UdpSocket<L>>> {
+        self.get(handle)
+    }
+
+    /// Enqueue data received from the modem into the receive buffer of the
+    /// socket with the given handle, and return the amount of octets stored.
+    ///
+    /// This function returns `Err(Error::InvalidSocket)` if the handle is unknown.
+    pub fn ingest(&mut self, handle: Handle, data: &[u8]) -> Result<usize> {
+        let index = self.index_of(handle)?;
+
+        match self.sockets.get_mut(index).ok_or(Error::InvalidSocket)? {
+            Some(socket) => Ok(socket.rx_enqueue_slice(data)),
+            None => Err(Error::InvalidSocket),
+        }
+    }
+
     /// Get the index of a given socket in the set.
     fn index_of(&self, handle: Handle) -> Result<usize> {
         self.sockets
@@ -107,6 +536,30 @@ impl<const N: usize, const L: usize> Set<N, L> {
             .ok_or(Error::InvalidSocket)
     }
 
+    /// Replace the socket with the given handle by `socket`, in place, and
+    /// return the replaced socket.
+    ///
+    /// Unlike a [remove](#method.remove) followed by an [add](#method.add),
+    /// the handle is never free in between, e.g. to repurpose the slot of a
+    /// recycled TCP socket for UDP. The new socket is given a new generation,
+    /// see [GenerationalHandle].
+    ///
+    /// This function returns `Err(Error::InvalidSocket)` if the handle is
+    /// unknown, and `Err(Error::Illegal)` if `socket` has another handle.
+    pub fn replace(&mut self, handle: Handle, mut socket: Socket<L>) -> Result<Socket<L>> {
+        let index = self.index_of(handle)?;
+        if socket.handle() != handle {
+            return Err(Error::Illegal);
+        }
+
+        socket.meta_mut().generation = self.next_generation;
+        let old = self.sockets[index]
+            .replace(socket)
+            .ok_or(Error::InvalidSocket)?;
+        self.next_generation = self.next_generation.wrapping_add(1);
+        Ok(old)
+    }
+
     /// Remove a socket from the set
     pub fn remove(&mut self, handle: Handle) -> Result<()> {
         let index = self.index_of(handle)?;
@@ -133,15 +586,135 @@ impl<const N: usize, const L: usize> Set<N, L> {
         })
     }
 
-    pub fn recycle(&mut self) -> bool {
-        let h = self.iter().find(|(_, s)| s.recycle()).map(|(h, _)| h);
-        if h.is_none() {
-            return false;
+    /// Remove and drop all sockets in this set, and return the amount of
+    /// sockets removed.
+    pub fn clear(&mut self) -> usize {
+        debug!("[Socket Set] Clearing: {:?}", self);
+        self.sockets
+            .iter_mut()
+            .filter_map(|slot| slot.take())
+            .count()
+    }
+
+    /// Reset every socket in this set, keeping their handles.
+    ///
+    /// This is intended for reconciliation after a modem reset, where the
+    /// modem no longer knows about any of the sockets, so they need to be
+    /// created and connected again.
+    pub fn reset_all(&mut self) {
+        self.reset_all_at(Instant::now())
+    }
+
+    /// Same as [reset_all](#method.reset_all), but with `now` as the current
+    /// time.
+    pub fn reset_all_at(&mut self, now: Instant) {
+        debug!("[Socket Set] Resetting: {:?}", self);
+        for (_, mut socket) in self.iter_mut() {
+            socket.reset_at(now);
         }
-        self.remove(h.unwrap()).is_ok()
+    }
+
+    /// Remove the first socket that should be recycled (see
+    /// [Socket::recycle]), and return its handle, along with why its
+    /// connection was closed.
+    ///
+    /// A TCP socket is recycled once its read timeout expires, so its close
+    /// reason is [CloseReason::Timeout]. UDP sockets have no close reason.
+    pub fn recycle(&mut self) -> Option<(Handle, Option<CloseReason>)> {
+        self.recycle_at(Instant::now())
+    }
+
+    /// Same as [recycle](#method.recycle), but with `now` as the current time.
+    pub fn recycle_at(&mut self, now: Instant) -> Option<(Handle, Option<CloseReason>)> {
+        let (h, ty) = self
+            .iter()
+            .find(|(_, s)| s.recycle_at(now))
+            .map(|(h, s)| (h, s.get_type()))?;
+        self.remove(h).ok()?;
+
+        let reason = match ty {
+            SocketType::Tcp => Some(CloseReason::Timeout),
+            SocketType::Udp => None,
+        };
+        Some((h, reason))
+    }
+
+    /// Get the handles of all sockets that should have their available data
+    /// updated from the modem.
+    ///
+    /// Every returned socket is marked as checked, see
+    /// [`Socket::should_update_available_data`].
+    pub fn sockets_due_for_poll(&mut self) -> Vec<Handle, N> {
+        self.sockets_due_for_poll_at(Instant::now())
+    }
+
+    /// Same as [sockets_due_for_poll](#method.sockets_due_for_poll), but with
+    /// `now` as the current time.
+    pub fn sockets_due_for_poll_at(&mut self, now: Instant) -> Vec<Handle, N> {
+        self.sockets
+            .iter_mut()
+            .filter_map(|slot| slot.as_mut())
+            .filter_map(|socket| {
+                socket
+                    .should_update_available_data_at(now)
+                    .then(|| socket.handle())
+            })
+            .collect()
+    }
+
+    /// Get the earliest time any socket in this set needs attention, along
+    /// with its handle and the reason, or `None` if no socket has a deadline.
+    ///
+    /// This lets the driver sleep until the deadline, and act on the reported
+    /// socket without scanning the set again. Ties go to the lowest handle.
+    pub fn next_deadline(&self) -> Option<(Handle, Instant, PollReason)> {
+        self.next_deadline_at(Instant::now())
+    }
+
+    /// Same as [next_deadline](#method.next_deadline), but with `now` as the
+    /// current time.
+    pub fn next_deadline_at(&self, now: Instant) -> Option<(Handle, Instant, PollReason)> {
+        self.iter()
+            .filter_map(|(handle, socket)| {
+                socket
+                    .next_deadline_at(now)
+                    .map(|(at, reason)| (handle, at, reason))
+            })
+            .min_by_key(|&(_, at, _)| at)
+    }
+
+    /// Get the handle of the next socket to service, in round-robin order,
+    /// or `None` if no socket is ready.
+    ///
+    /// A socket is ready when the modem has data available for it, and it has
+    /// room to receive it. Each call continues after the socket returned by
+    /// the previous call, in ascending handle order, so a busy socket cannot
+    /// starve the others.
+    pub fn next_serviceable(&mut self) -> Option<Handle> {
+        let handle = self
+            .serviceable()
+            .find(|&handle| Some(handle) > self.last_serviced)
+            .or_else(|| self.serviceable().next())?;
+
+        self.last_serviced = Some(handle);
+        Some(handle)
+    }
+
+    fn serviceable(&self) -> impl Iterator<Item = Handle> + '_ {
+        self.iter()
+            .filter(|(_, socket)| socket.available_data() > 0 && socket.can_recv())
+            .map(|(handle, _)| handle)
+    }
+
+    /// Get the handle of the `n`th socket in iteration order, see [iter](#method.iter).
+    pub fn nth_handle(&self, n: usize) -> Option<Handle> {
+        self.iter().nth(n).map(|(handle, _)| handle)
     }
 
     /// Iterate every socket in this set.
+    ///
+    /// Sockets are always yielded in ascending handle order, as long as their
+    /// handles are not updated after being added to the set.
     pub fn iter(&self) -> impl Iterator<Item = (Handle, &Socket<L>)> {
         self.sockets.iter().filter_map(|slot| {
             slot.as_ref()
@@ -149,7 +722,45 @@ impl<const N: usize, const L: usize> Set<N, L> {
         })
     }
 
+    /// Iterate the handles of every socket in this set that can receive data,
+    /// see [`Socket::can_recv`].
+    pub fn readable(&self) -> impl Iterator<Item = Handle> + '_ {
+        self.iter()
+            .filter(|(_, socket)| socket.can_recv())
+            .map(|(handle, _)| handle)
+    }
+
+    /// Iterate every socket in this set that can receive data, as SocketRef,
+    /// see [readable](#method.readable).
+    ///
+    /// This allows servicing the ready sockets in a single pass, without
+    /// looking them up again by handle.
+    pub fn iter_ready_mut(&mut self) -> impl Iterator<Item = SocketRef<'_, Socket<L>>> {
+        self.sockets
+            .iter_mut()
+            .filter_map(|slot| slot.as_mut())
+            .filter(|socket| socket.can_recv())
+            .map(SocketRef::new)
+    }
+
+    /// Write the handles of every socket in this set that can receive data
+    /// into `out`, see [readable](#method.readable), and return the amount of
+    /// handles written.
+    ///
+    /// Handles that do not fit in `out` are silently left out, so size it to
+    /// [CAPACITY](#associatedconstant.CAPACITY) to never miss any.
+    pub fn collect_ready(&self, out: &mut [Handle]) -> usize {
+        let mut count = 0;
+        for (slot, handle) in out.iter_mut().zip(self.readable()) {
+            *slot = handle;
+            count += 1;
+        }
+        count
+    }
+
     /// Iterate every socket in this set, as SocketRef.
+    ///
+    /// Sockets are yielded in the same order as [iter](#method.iter).
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle, SocketRef<Socket<L>>)> {
         self.sockets.iter_mut().filter_map(|slot| {
             slot.as_mut()
@@ -175,7 +786,35 @@ impl<const N: usize, const L: usize> defmt::Format for Set<N, L> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{TcpSocket, UdpSocket};
+
+    #[test]
+    fn capacity() {
+        const CAPACITY: usize = Set::<4, 64>::CAPACITY;
+        let handles = [Handle(0); CAPACITY];
+        assert_eq!(handles.len(), 4);
+        assert_eq!(Set::<4, 64>::new().capacity(), CAPACITY);
+    }
+
+    #[test]
+    fn total_buffer_bytes() {
+        const TOTAL: usize = Set::<4, 64>::total_buffer_bytes();
+        assert_eq!(TOTAL, 256);
+        assert_eq!(Set::<4, 64>::BYTES_PER_SOCKET, 64);
+    }
+
+    #[test]
+    fn from_sockets() {
+        let set = Set::<2, 64>::from_sockets([UdpSocket::new(1).into(), TcpSocket::new(0).into()])
+            .unwrap();
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.socket_type(Handle(0)), Some(SocketType::Tcp));
+        assert_eq!(set.socket_type(Handle(1)), Some(SocketType::Udp));
+
+        assert_eq!(
+            Set::<2, 64>::from_sockets([TcpSocket::new(0).into(), UdpSocket::new(0).into()]).err(),
+            Some(Error::DuplicateSocket)
+        );
+    }
 
     #[test]
     fn add_socket() {
@@ -256,6 +895,47 @@ mod tests {
             .expect("failed to get udp socket");
     }
 
+    #[test]
+    fn get_typed_socket() {
+        let mut set = Set::<2, 64>::new();
+
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+        assert_eq!(set.add(UdpSocket::new(1)), Ok(Handle(1)));
+
+        assert_eq!(set.get_tcp(Handle(0)).map(|s| s.handle()), Ok(Handle(0)));
+        assert_eq!(set.get_udp(Handle(1)).map(|s| s.handle()), Ok(Handle(1)));
+
+        assert!(matches!(set.get_tcp(Handle(1)), Err(Error::Illegal)));
+        assert!(matches!(set.get_udp(Handle(0)), Err(Error::Illegal)));
+        assert!(matches!(set.get_tcp(Handle(2)), Err(Error::InvalidSocket)));
+    }
+
+    #[test]
+    fn ingest_data() {
+        let mut set = Set::<2, 8>::new();
+
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+
+        assert_eq!(set.ingest(Handle(0), b"hello"), Ok(5));
+        assert_eq!(set.ingest(Handle(0), b"world"), Ok(3));
+        assert_eq!(set.ingest(Handle(1), b"hello"), Err(Error::InvalidSocket));
+        assert_eq!(set.get_tcp(Handle(0)).unwrap().recv_queue(), 8);
+    }
+
+    #[test]
+    fn readable_sockets() {
+        let mut set = Set::<2, 4>::new();
+
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+        assert_eq!(set.add(UdpSocket::new(1)), Ok(Handle(1)));
+
+        // A fresh TCP socket is not connected, so only the UDP socket is readable
+        assert!(set.readable().eq([Handle(1)]));
+
+        set.get_udp(Handle(1)).unwrap().rx_enqueue_slice(b"full");
+        assert_eq!(set.readable().count(), 0);
+    }
+
     #[test]
     fn get_socket_type() {
         let mut set = Set::<2, 64>::new();
@@ -267,6 +947,10 @@ mod tests {
 
         assert_eq!(set.socket_type(Handle(0)), Some(SocketType::Tcp));
         assert_eq!(set.socket_type(Handle(1)), Some(SocketType::Udp));
+        assert_eq!(set.socket_type(Handle(2)), None);
+
+        assert!(set.remove(Handle(0)).is_ok());
+        assert_eq!(set.socket_type(Handle(0)), None);
     }
 
     #[test]
@@ -293,6 +977,28 @@ mod tests {
             .expect("failed to get tcp socket");
     }
 
+    #[test]
+    fn replace_in_place() {
+        let mut set = Set::<2, 64>::new();
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+        let handle = set.generational_handle(Handle(0)).unwrap();
+
+        let old = set.replace(Handle(0), UdpSocket::new(0).into()).unwrap();
+        assert_eq!(old.get_type(), SocketType::Tcp);
+        assert_eq!(set.socket_type(Handle(0)), Some(SocketType::Udp));
+        assert_eq!(set.len(), 1);
+        assert!(set.get_generational::<UdpSocket<64>>(handle).is_err());
+
+        assert_eq!(
+            set.replace(Handle(0), TcpSocket::new(1).into()).err(),
+            Some(Error::Illegal)
+        );
+        assert_eq!(
+            set.replace(Handle(1), TcpSocket::new(1).into()).err(),
+            Some(Error::InvalidSocket)
+        );
+    }
+
     #[test]
     fn prune_socket_set() {
         let mut set = Set::<2, 64>::new();
@@ -308,4 +1014,475 @@ mod tests {
         set.prune();
         assert_eq!(set.len(), 0);
     }
+
+    #[test]
+    fn clear_socket_set() {
+        let mut set = Set::<2, 64>::new();
+
+        assert_eq!(set.clear(), 0);
+
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+        assert_eq!(set.add(UdpSocket::new(1)), Ok(Handle(1)));
+
+        assert_eq!(set.clear(), 2);
+        assert!(set.is_empty());
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+    }
+
+    #[test]
+    fn display_handle() {
+        assert_eq!(format!("socket {}", Handle(3)), "socket 3");
+    }
+
+    #[test]
+    fn reset_all_sockets() {
+        let mut set = Set::<2, 64>::new();
+
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+        assert_eq!(set.add(UdpSocket::new(1)), Ok(Handle(1)));
+        assert_eq!(set.ingest(Handle(0), b"hello"), Ok(5));
+        set.get_udp(Handle(1))
+            .unwrap()
+            .bind(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000))
+            .unwrap();
+
+        set.reset_all();
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get_tcp(Handle(0)).unwrap().recv_queue(), 0);
+        assert!(!set.get_udp(Handle(1)).unwrap().is_open());
+    }
+
+    #[test]
+    fn reserve_socket() {
+        let reserved = Instant::from_secs(100);
+        let mut set = Set::<3, 64>::new();
+
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+        assert_eq!(set.reserve_at(SocketType::Udp, reserved), Ok(Handle(1)));
+        assert_eq!(set.socket_type(Handle(1)), Some(SocketType::Udp));
+        assert!(set.iter().any(|(h, s)| h == Handle(1) && s.is_pending()));
+
+        assert_eq!(set.confirm(Handle(1)), Ok(()));
+        assert_eq!(set.confirm(Handle(1)), Err(Error::Illegal));
+        assert_eq!(set.confirm(Handle(0)), Err(Error::Illegal));
+
+        assert_eq!(set.reserve_at(SocketType::Tcp, reserved), Ok(Handle(2)));
+        assert_eq!(
+            set.reserve_at(SocketType::Tcp, reserved),
+            Err(Error::SocketSetFull)
+        );
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(set.reclaim_pending_at(timeout, reserved), 0);
+        assert_eq!(
+            set.reclaim_pending_at(timeout, reserved + Duration::from_secs(10)),
+            1
+        );
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.socket_type(Handle(2)), None);
+    }
+
+    #[test]
+    fn reserve_socket_ids() {
+        let reserved = Instant::from_secs(100);
+        let mut set = Set::<12, 8>::new();
+        assert_eq!(set.add(UdpSocket::new(5)), Ok(Handle(5)));
+
+        for id in (0..=9).filter(|id| *id != 5) {
+            assert_eq!(set.reserve_at(SocketType::Tcp, reserved), Ok(Handle(id)));
+        }
+
+        // There is room in the set, but no socket id left for the module
+        assert_eq!(
+            set.reserve_at(SocketType::Tcp, reserved),
+            Err(Error::SocketSetFull)
+        );
+        assert_eq!(set.len(), 10);
+    }
+
+    #[test]
+    fn iteration_order() {
+        let mut set = Set::<3, 64>::new();
+
+        assert_eq!(set.add(UdpSocket::new(2)), Ok(Handle(2)));
+        assert_eq!(set.add(TcpSocket::new(0)), Ok(Handle(0)));
+        assert_eq!(set.add(TcpSocket::new(1)), Ok(Handle(1)));
+        assert!(set
+            .iter()
+            .map(|(h, _)| h)
+            .eq([Handle(0), Handle(1), Handle(2)]));
+
+        assert!(set.remove(Handle(1)).is_ok());
+        assert_eq!(set.add(UdpSocket::new(1)), Ok(Handle(1)));
+        assert!(set
+            .iter_mut()
+            .map(|(h, _)| h)
+            .eq([Handle(0), Handle(1), Handle(2)]));
+
+        assert_eq!(set.nth_handle(0), Some(Handle(0)));
+        assert_eq!(set.nth_handle(2), Some(Handle(2)));
+        assert_eq!(set.nth_handle(3), None);
+    }
+
+    #[test]
+    fn snapshot_and_restore() {
+        let closed = Instant::from_secs(100);
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        let remote_v6 = SocketAddr::V6(SocketAddrV6::new(
+            Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+            2000,
+            0,
+            3,
+        ));
+        let mut set = Set::<3, 64>::new();
+
+        let mut tcp = TcpSocket::new(0);
+        tcp.set_state(TcpState::Connected(remote));
+        assert_eq!(set.add(tcp), Ok(Handle(0)));
+        let mut udp = UdpSocket::new(1);
+        udp.bind(remote_v6).unwrap();
+        assert_eq!(set.add(udp), Ok(Handle(1)));
+        assert_eq!(set.add(TcpSocket::new(2)), Ok(Handle(2)));
+
+        let mut data = [0; 64];
+        let len = set.snapshot(&mut data);
+        assert_eq!(len, (3 + 7) + (3 + 23) + (3 + 1));
+
+        // Only whole sockets are written
+        assert_eq!(set.snapshot(&mut data[..12]), 10);
+
+        let mut restored = Set::<3, 64>::new();
+        assert_eq!(restored.add(UdpSocket::new(5)), Ok(Handle(5)));
+        assert_eq!(restored.restore_at(&data[..len], closed), Ok(()));
+        assert_eq!(restored.len(), 3);
+        assert_eq!(restored.socket_type(Handle(5)), None);
+        assert_eq!(
+            restored.get_tcp(Handle(0)).unwrap().state(),
+            &TcpState::Connected(remote)
+        );
+        assert_eq!(
+            restored.get_udp(Handle(1)).unwrap().endpoint(),
+            Some(remote_v6)
+        );
+        assert_eq!(
+            restored.get_tcp(Handle(2)).unwrap().state(),
+            &TcpState::Created
+        );
+
+        // Truncated snapshots leave the set unchanged
+        assert_eq!(
+            restored.restore_at(&data[..len - 1], closed),
+            Err(Error::BadLength)
+        );
+        assert_eq!(restored.len(), 3);
+    }
+
+    #[test]
+    fn next_serviceable() {
+        let mut set = Set::<4, 64>::new();
+        assert_eq!(set.next_serviceable(), None);
+
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        for id in 0..3 {
+            let mut socket = TcpSocket::new(id);
+            socket.set_state(TcpState::Connected(remote));
+            socket.set_available_data(10);
+            assert_eq!(set.add(socket), Ok(Handle(id)));
+        }
+        assert_eq!(set.add(TcpSocket::new(3)), Ok(Handle(3)));
+
+        let order: Vec<Handle, 7> = (0..7).filter_map(|_| set.next_serviceable()).collect();
+        assert_eq!(order.as_slice(), &[0, 1, 2, 0, 1, 2, 0].map(Handle));
+
+        // Sockets that are not ready are skipped
+        set.get_tcp(Handle(2)).unwrap().set_available_data(0);
+        assert_eq!(set.next_serviceable(), Some(Handle(1)));
+        assert_eq!(set.next_serviceable(), Some(Handle(0)));
+    }
+
+    #[test]
+    fn collect_ready() {
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        let mut set = Set::<4, 64>::new();
+        for id in 0..4 {
+            let mut socket = TcpSocket::new(id);
+            if id != 1 {
+                socket.set_state(TcpState::Connected(remote));
+            }
+            assert_eq!(set.add(socket), Ok(Handle(id)));
+        }
+
+        let mut out = [Handle(9); Set::<4, 64>::CAPACITY];
+        assert_eq!(set.collect_ready(&mut out), 3);
+        assert_eq!(&out[..3], &[Handle(0), Handle(2), Handle(3)]);
+
+        // Handles beyond the length of `out` are left out
+        let mut out = [Handle(9); 2];
+        assert_eq!(set.collect_ready(&mut out), 2);
+        assert_eq!(out, [Handle(0), Handle(2)]);
+    }
+
+    #[test]
+    fn next_deadline() {
+        let start = Instant::from_secs(100);
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        let mut set = Set::<4, 64>::new();
+        assert_eq!(set.next_deadline_at(start), None);
+
+        for id in 0..2 {
+            let mut socket = TcpSocket::new(id);
+            socket.set_state(TcpState::Connected(remote));
+            assert!(socket.should_update_available_data_at(start));
+            assert_eq!(set.add(socket), Ok(Handle(id)));
+        }
+        assert_eq!(
+            set.next_deadline_at(start),
+            Some((
+                Handle(0),
+                start + Duration::from_secs(15),
+                PollReason::DataPoll
+            ))
+        );
+
+        set.get_tcp(Handle(1))
+            .unwrap()
+            .closed_by_remote_at(start - Duration::from_secs(5));
+        assert_eq!(
+            set.next_deadline_at(start),
+            Some((
+                Handle(1),
+                start + Duration::from_secs(10),
+                PollReason::RecycleDue
+            ))
+        );
+    }
+
+    #[test]
+    fn get_generational() {
+        let mut set = Set::<2, 64>::new();
+        set.add(TcpSocket::new(0)).unwrap();
+        let handle = set.generational_handle(Handle(0)).unwrap();
+        assert!(set.get_generational::<TcpSocket<64>>(handle).is_ok());
+
+        // A handle reused by a new socket is detected
+        set.remove(Handle(0)).unwrap();
+        assert_eq!(
+            set.get_generational::<TcpSocket<64>>(handle).err(),
+            Some(Error::InvalidSocket)
+        );
+        set.add(TcpSocket::new(0)).unwrap();
+        assert_eq!(
+            set.get_generational::<TcpSocket<64>>(handle).err(),
+            Some(Error::InvalidSocket)
+        );
+
+        let handle = set.generational_handle(Handle(0)).unwrap();
+        assert!(set.get_generational::<TcpSocket<64>>(handle).is_ok());
+        assert_eq!(
+            set.generational_handle(Handle(1)),
+            Err(Error::InvalidSocket)
+        );
+    }
+
+    #[test]
+    fn iter_ready_mut() {
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        let mut set = Set::<4, 64>::new();
+        for id in 0..3 {
+            let mut socket = TcpSocket::new(id);
+            if id != 1 {
+                socket.set_state(TcpState::Connected(remote));
+            }
+            assert_eq!(set.add(socket), Ok(Handle(id)));
+        }
+
+        for mut socket in set.iter_ready_mut() {
+            assert_eq!(socket.rx_enqueue_slice(b"abc"), 3);
+        }
+        let used: Vec<usize, 3> = set.iter().map(|(_, s)| s.buffer_used()).collect();
+        assert_eq!(used.as_slice(), &[3, 0, 3]);
+    }
+
+    #[test]
+    fn find_by_endpoint() {
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        let other = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 1000));
+        let mut set = Set::<4, 64>::new();
+        assert_eq!(set.find_by_endpoint(remote), None);
+
+        let mut socket = UdpSocket::new(0);
+        socket.bind(other).unwrap();
+        set.add(socket).unwrap();
+        let mut socket = TcpSocket::new(1);
+        socket.set_state(TcpState::Connected(remote));
+        set.add(socket).unwrap();
+
+        assert_eq!(set.find_by_endpoint(remote), Some(Handle(1)));
+        assert_eq!(set.find_by_endpoint(other), Some(Handle(0)));
+    }
+
+    #[test]
+    fn empty_set() {
+        let now = Instant::from_secs(100);
+        let mut set = Set::<4, 64>::new();
+        assert_eq!(set.iter().count(), 0);
+        assert_eq!(set.iter_mut().count(), 0);
+        assert_eq!(set.next_serviceable(), None);
+        assert_eq!(set.next_deadline_at(now), None);
+        assert!(set.sockets_due_for_poll().is_empty());
+        assert_eq!(set.recycle(), None);
+        assert_eq!(set.clear(), 0);
+        set.prune();
+        assert!(set.is_empty());
+
+        // The first reserved handle is 0
+        assert_eq!(set.reserve_at(SocketType::Tcp, now), Ok(Handle(0)));
+    }
+
+    #[test]
+    fn single_socket() {
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        let mut set = Set::<4, 64>::new();
+        let mut socket = TcpSocket::new(2);
+        socket.set_state(TcpState::Connected(remote));
+        socket.set_available_data(10);
+        set.add(socket).unwrap();
+
+        // The round-robin cursor wraps around to the only socket
+        for _ in 0..3 {
+            assert_eq!(set.next_serviceable(), Some(Handle(2)));
+        }
+        assert_eq!(set.nth_handle(0), Some(Handle(2)));
+        assert_eq!(set.nth_handle(1), None);
+
+        set.remove(Handle(2)).unwrap();
+        assert_eq!(set.next_serviceable(), None);
+    }
+
+    #[test]
+    fn externally_driven_timers() {
+        let start = Instant::from_secs(100);
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        let mut set = Set::<4, 64>::new();
+        for id in 0..2 {
+            let mut socket = TcpSocket::new(id);
+            socket.set_state(TcpState::Connected(remote));
+            assert_eq!(set.add(socket), Ok(Handle(id)));
+        }
+
+        assert_eq!(
+            set.sockets_due_for_poll_at(start).as_slice(),
+            &[Handle(0), Handle(1)]
+        );
+        assert!(set.sockets_due_for_poll_at(start).is_empty());
+
+        set.get_tcp(Handle(1)).unwrap().closed_by_remote_at(start);
+        assert_eq!(set.recycle_at(start + Duration::from_secs(14)), None);
+        assert_eq!(
+            set.recycle_at(start + Duration::from_secs(15)),
+            Some((Handle(1), Some(CloseReason::Timeout)))
+        );
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn recycle_close_reason() {
+        let closed = Instant::from_secs(100);
+        let mut set = Set::<2, 64>::new();
+        set.add(UdpSocket::new(0)).unwrap();
+        let mut socket = TcpSocket::new(1);
+        socket.set_state(TcpState::Connected(SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::new(10, 0, 0, 1),
+            1000,
+        ))));
+        set.add(socket).unwrap();
+
+        set.get_udp(Handle(0)).unwrap().closed_by_remote_at(closed);
+        set.get_tcp(Handle(1))
+            .unwrap()
+            .closed_by_remote_at(closed + Duration::from_secs(5));
+
+        let recycled = closed + Duration::from_secs(20);
+        assert_eq!(set.recycle_at(recycled), Some((Handle(0), None)));
+        assert_eq!(
+            set.recycle_at(recycled),
+            Some((Handle(1), Some(CloseReason::Timeout)))
+        );
+        assert_eq!(set.recycle_at(recycled), None);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn buffer_usage() {
+        let mut set = Set::<2, 64>::new();
+        set.add(TcpSocket::new(0)).unwrap();
+        set.add(UdpSocket::new(1)).unwrap();
+        set.get_tcp(Handle(0)).unwrap().rx_enqueue_slice(b"hello");
+
+        let usage: Vec<(usize, usize), 2> = set
+            .iter()
+            .map(|(_, s)| (s.buffer_capacity(), s.buffer_used()))
+            .collect();
+        assert_eq!(usage.as_slice(), &[(64, 5), (64, 0)]);
+    }
+
+    #[test]
+    fn sockets_due_for_poll() {
+        let start = Instant::from_secs(100);
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 1000));
+        let mut set = Set::<4, 64>::new();
+
+        let mut socket = TcpSocket::new(0);
+        socket.set_state(TcpState::Connected(remote));
+        set.add(socket).unwrap();
+        set.add(TcpSocket::new(1)).unwrap();
+        let mut socket = TcpSocket::new(2);
+        socket.set_state(TcpState::Connected(remote));
+        socket.mark_data_available(10);
+        set.add(socket).unwrap();
+
+        // Only connected sockets without pushed data are due
+        assert_eq!(set.sockets_due_for_poll_at(start).as_slice(), &[Handle(0)]);
+
+        // Due sockets are marked as checked until the check interval has passed
+        assert!(set
+            .sockets_due_for_poll_at(start + Duration::from_secs(14))
+            .is_empty());
+        assert_eq!(
+            set.sockets_due_for_poll_at(start + Duration::from_secs(15))
+                .as_slice(),
+            &[Handle(0)]
+        );
+    }
+
+    #[test]
+    fn get_or_insert_with() {
+        let mut set = Set::<2, 64>::new();
+
+        let socket = set
+            .get_or_insert_with(Handle(1), || UdpSocket::new(1).into())
+            .unwrap();
+        assert_eq!(socket.get_type(), SocketType::Udp);
+
+        // An existing socket is returned without calling `f`
+        let socket = set
+            .get_or_insert_with(Handle(1), || unreachable!())
+            .unwrap();
+        assert_eq!(socket.get_type(), SocketType::Udp);
+        assert_eq!(set.len(), 1);
+
+        assert_eq!(
+            set.get_or_insert_with(Handle(2), || TcpSocket::new(3).into())
+                .err(),
+            Some(Error::Illegal)
+        );
+        assert!(set
+            .get_or_insert_with(Handle(2), || TcpSocket::new(2).into())
+            .is_ok());
+        assert_eq!(
+            set.get_or_insert_with(Handle(3), || TcpSocket::new(3).into())
+                .err(),
+            Some(Error::SocketSetFull)
+        );
+    }
 }
@@ -1,7 +1,65 @@
-use no_std_net::SocketAddr;
+use core::cmp::min;
+use core::fmt::Write;
 
-use super::{Error, Result, RingBuffer, Socket, SocketHandle, SocketMeta};
+use super::{
+    Error, PollReason, Result, RingBuffer, Socket, SocketAddr, SocketHandle, SocketMeta,
+    SocketType, Watermarks, DEFAULT_CHECK_INTERVAL, DEFAULT_READ_TIMEOUT, MAX_SECURITY_PROFILE,
+};
+#[cfg(any(test, feature = "testing"))]
+use super::{Ipv4Addr, SocketAddrV4};
+#[cfg(feature = "async")]
+use core::task::Waker;
 use embassy_time::{Duration, Instant};
+#[cfg(feature = "trace-history")]
+use heapless::HistoryBuffer;
+
+/// The reconnect backoff after a single connection attempt, doubled for every
+/// further attempt, see [TcpSocket::backoff_hint].
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// The longest reconnect backoff, see [TcpSocket::backoff_hint].
+const BACKOFF_MAX: Duration = Duration::from_secs(64);
+
+/// The reflected CRC-32 (IEEE 802.3) polynomial, see [TcpSocket::rx_running_crc].
+const CRC32_POLY: u32 = 0xEDB8_8320;
+
+/// Continue the CRC-32 `crc` over `data`, bit by bit to avoid a lookup table.
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &octet in data {
+        crc ^= u32::from(octet);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (CRC32_POLY & 0u32.wrapping_sub(crc & 1));
+        }
+    }
+    !crc
+}
+
+/// The amount of buffered octets included in a [TcpSocket::debug_dump].
+const DEBUG_DUMP_OCTETS: usize = 8;
+
+/// A formatter sink writing into a byte slice, truncating at its end.
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let n = min(s.len(), self.buf.len() - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        if n < s.len() {
+            Err(core::fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The amount of state transitions kept, see [TcpSocket::state_history].
+#[cfg(feature = "trace-history")]
+pub const STATE_HISTORY_LEN: usize = 8;
 
 /// A TCP socket ring buffer.
 pub type SocketBuffer<const N: usize> = RingBuffer<u8, N>;
@@ -18,6 +76,30 @@ pub enum State {
     ShutdownForWrite(Instant),
 }
 
+/// The variant of a [State], without its data, as recorded in the
+/// [state history](TcpSocket::state_history).
+#[cfg(feature = "trace-history")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StateKind {
+    Created,
+    WaitingForConnect,
+    Connected,
+    ShutdownForWrite,
+}
+
+#[cfg(feature = "trace-history")]
+impl From<&State> for StateKind {
+    fn from(state: &State) -> Self {
+        match state {
+            State::Created => StateKind::Created,
+            State::WaitingForConnect(_) => StateKind::WaitingForConnect,
+            State::Connected(_) => StateKind::Connected,
+            State::ShutdownForWrite(_) => StateKind::ShutdownForWrite,
+        }
+    }
+}
+
 #[cfg(feature = "defmt")]
 impl defmt::Format for State {
     fn format(&self, fmt: defmt::Formatter) {
@@ -30,6 +112,36 @@ impl defmt::Format for State {
     }
 }
 
+/// The receive status of a TCP socket, see [TcpSocket::recv_status].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecvStatus {
+    /// The given amount of octets can be received right away.
+    Data(usize),
+    /// The receive buffer is empty, but more data may arrive.
+    WouldBlock,
+    /// The receive buffer is empty, and the receive half of the connection is closed.
+    Closed,
+}
+
+/// The reason a TCP connection was closed, see [TcpSocket::close_reason].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CloseReason {
+    /// The connection was closed by the remote.
+    RemoteReset,
+    /// The connection was closed by the application.
+    LocalClose,
+    /// The connection timed out, e.g. a socket closed by the remote was kept
+    /// for the read timeout, see [SocketSet::recycle](crate::SocketSet::recycle).
+    Timeout,
+    /// The modem reported an error on the connection.
+    ModemError,
+    /// The connection was closed for being idle, see
+    /// [TcpSocket::should_close_idle].
+    IdleTimeout,
+}
+
 /// A Transmission Control Protocol socket.
 ///
 /// A TCP socket may passively listen for connections or actively connect to another endpoint.
@@ -40,29 +152,99 @@ impl defmt::Format for State {
 pub struct TcpSocket<const L: usize> {
     pub(crate) meta: SocketMeta,
     state: State,
+    was_connected: bool,
+    connect_attempts: u8,
+    close_reason: Option<CloseReason>,
     check_interval: Duration,
     read_timeout: Option<Duration>,
     available_data: usize,
+    available_data_pushed: bool,
     rx_buffer: SocketBuffer<L>,
+    watermarks: Watermarks,
+    rx_cap: usize,
+    paused: bool,
+    expected_offset: Option<u32>,
+    rx_crc: u32,
+    high_water_mark: usize,
+    token: u32,
+    last_error: Option<Error>,
+    last_error_at: Option<Instant>,
+    #[cfg(feature = "async")]
+    rx_waker: Option<Waker>,
+    #[cfg(feature = "trace-history")]
+    state_history: HistoryBuffer<(StateKind, Instant), STATE_HISTORY_LEN>,
+    ttl: Option<u8>,
+    dscp: Option<u8>,
+    security_profile: Option<u8>,
     last_check_time: Option<Instant>,
+    keepalive: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    last_activity: Option<Instant>,
+    rx_activity: bool,
 }
 
 impl<const L: usize> TcpSocket<L> {
     /// Create a socket using the given buffers.
-    pub fn new(socket_id: u8) -> TcpSocket<L> {
+    ///
+    /// This is a `const fn`, and can be used to initialize a socket in a `static`.
+    pub const fn new(socket_id: u8) -> TcpSocket<L> {
         TcpSocket {
             meta: SocketMeta {
                 handle: SocketHandle(socket_id),
+                ty: SocketType::Tcp,
+                pending_since: None,
+                generation: 0,
             },
-            state: State::default(),
-            rx_buffer: SocketBuffer::new(),
+            state: State::Created,
+            was_connected: false,
+            connect_attempts: 0,
+            close_reason: None,
+            rx_buffer: SocketBuffer::from_storage([0; L]),
+            watermarks: Watermarks::for_capacity(L),
+            rx_cap: L,
+            paused: false,
+            expected_offset: None,
+            rx_crc: 0,
+            high_water_mark: 0,
+            token: 0,
+            last_error: None,
+            last_error_at: None,
+            #[cfg(feature = "async")]
+            rx_waker: None,
+            #[cfg(feature = "trace-history")]
+            state_history: HistoryBuffer::new(),
+            ttl: None,
+            dscp: None,
+            security_profile: None,
             available_data: 0,
-            check_interval: Duration::from_secs(15),
-            read_timeout: Some(Duration::from_secs(15)),
+            available_data_pushed: false,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
             last_check_time: None,
+            keepalive: None,
+            idle_timeout: None,
+            last_activity: None,
+            rx_activity: false,
         }
     }
 
+    /// Create a connected socket, with `data` already in its receive buffer,
+    /// to test application code against without going through a driver.
+    ///
+    /// The socket is connected to the unspecified address, and `data` is
+    /// truncated to fit the receive buffer. It is connected at tick zero, so
+    /// creating it does not need a time driver.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn with_rx_data(socket_id: u8, data: &[u8]) -> TcpSocket<L> {
+        let mut socket = TcpSocket::new(socket_id);
+        socket.set_state_at(
+            State::Connected(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))),
+            Instant::from_ticks(0),
+        );
+        socket.rx_enqueue_slice(data);
+        socket
+    }
+
     /// Return the socket handle.
     pub fn handle(&self) -> SocketHandle {
         self.meta.handle
@@ -77,6 +259,108 @@ impl<const L: usize> TcpSocket<L> {
         self.meta.update(handle)
     }
 
+    /// Return the user token associated with this socket.
+    pub fn token(&self) -> u32 {
+        self.token
+    }
+
+    /// Associate an application specific token with this socket, e.g. a
+    /// stream id, defaulting to `0`. It is cleared by [reset](#method.reset).
+    pub fn set_token(&mut self, token: u32) {
+        self.token = token;
+    }
+
+    /// Latch an error reported by the modem for this socket, e.g. a failed
+    /// write, so it can be surfaced to the application on its next poll.
+    ///
+    /// This replaces any error latched before. The latch is cleared by
+    /// [take_last_error](#method.take_last_error) and [reset](#method.reset).
+    pub fn set_last_error(&mut self, error: Error) {
+        self.set_last_error_at(error, Instant::now())
+    }
+
+    /// Same as [set_last_error](#method.set_last_error), but with `now` as the
+    /// time the error occurred.
+    pub fn set_last_error_at(&mut self, error: Error, now: Instant) {
+        debug!(
+            "[TCP Socket] [{:?}] Latching error {:?}",
+            self.handle(),
+            error
+        );
+        self.last_error = Some(error);
+        self.last_error_at = Some(now);
+    }
+
+    /// Return the time the last error was latched, if any, e.g. to hold off
+    /// reconnecting for a while after a failure.
+    ///
+    /// Unlike the error itself, it is kept by
+    /// [take_last_error](#method.take_last_error), and only cleared by
+    /// [reset](#method.reset).
+    pub fn last_error_at(&self) -> Option<Instant> {
+        self.last_error_at
+    }
+
+    /// Return and clear the latched error, if any.
+    pub fn take_last_error(&mut self) -> Option<Error> {
+        self.last_error.take()
+    }
+
+    /// Return whether an error is latched on this socket.
+    pub fn has_error(&self) -> bool {
+        self.last_error.is_some()
+    }
+
+    /// Return the time-to-live (or IPv6 hop limit) of outgoing packets, or
+    /// `None` to use the modem default.
+    pub fn ttl(&self) -> Option<u8> {
+        self.ttl
+    }
+
+    /// Set the time-to-live (or IPv6 hop limit) of outgoing packets, for the
+    /// driver to configure on the modem. It is cleared by [reset](#method.reset).
+    pub fn set_ttl(&mut self, ttl: Option<u8>) {
+        self.ttl = ttl;
+    }
+
+    /// Return the DSCP value of outgoing packets, or `None` to use the modem
+    /// default.
+    pub fn dscp(&self) -> Option<u8> {
+        self.dscp
+    }
+
+    /// Set the DSCP value of outgoing packets, for the driver to configure on
+    /// the modem. It is cleared by [reset](#method.reset).
+    ///
+    /// This function returns `Err(Error::Illegal)` if the value does not fit in
+    /// the 6 bit DSCP field, i.e. is greater than 63.
+    pub fn set_dscp(&mut self, dscp: Option<u8>) -> Result<()> {
+        if matches!(dscp, Some(dscp) if dscp > 63) {
+            return Err(Error::Illegal);
+        }
+        self.dscp = dscp;
+        Ok(())
+    }
+
+    /// Return the security profile (`+USECPRF`) of the socket, or `None` if
+    /// it is not secured.
+    pub fn security_profile(&self) -> Option<u8> {
+        self.security_profile
+    }
+
+    /// Set the security profile (`+USECPRF`) of the socket, for the driver to
+    /// configure on the modem. It is cleared by [reset](#method.reset).
+    ///
+    /// This function returns `Err(Error::Illegal)` if the profile id is not
+    /// supported by the modem, i.e. is greater than [MAX_SECURITY_PROFILE].
+    pub fn set_security_profile(&mut self, profile: Option<u8>) -> Result<()> {
+        if matches!(profile, Some(profile) if profile > MAX_SECURITY_PROFILE) {
+            return Err(Error::Illegal);
+        }
+        self.security_profile = profile;
+        Ok(())
+    }
+
     /// Return the bound endpoint.
     pub fn endpoint(&self) -> Option<SocketAddr> {
         match self.state {
@@ -91,39 +375,252 @@ impl<const L: usize> TcpSocket<L> {
     }
 
     pub fn reset(&mut self) {
-        self.set_state(State::default());
+        self.reset_at(Instant::now())
+    }
+
+    /// Same as [reset](#method.reset), but with `now` as the current time.
+    pub fn reset_at(&mut self, now: Instant) {
+        self.set_state_at(State::default(), now);
         self.rx_buffer.clear();
         self.set_available_data(0);
         self.last_check_time = None;
+        self.last_activity = None;
+        self.rx_activity = false;
+        self.token = 0;
+        self.last_error = None;
+        self.last_error_at = None;
+        self.ttl = None;
+        self.dscp = None;
+        self.security_profile = None;
+        self.was_connected = false;
+        self.paused = false;
+        self.expected_offset = None;
+        self.rx_crc = 0;
+    }
+
+    /// Restart the available data check interval, and the recycle countdown
+    /// of a socket closed by the remote, without touching buffers or state.
+    pub fn reset_timers(&mut self) {
+        self.reset_timers_at(Instant::now())
+    }
+
+    /// Same as [reset_timers](#method.reset_timers), but with `now` as the
+    /// current time.
+    pub fn reset_timers_at(&mut self, now: Instant) {
+        self.last_check_time = None;
+        if let State::ShutdownForWrite(ref mut closed_time) = self.state {
+            *closed_time = now;
+        }
     }
 
     pub fn should_update_available_data(&mut self) -> bool {
+        self.should_update_available_data_at(Instant::now())
+    }
+
+    /// Same as [should_update_available_data](#method.should_update_available_data),
+    /// but with `now` as the current time.
+    pub fn should_update_available_data_at(&mut self, now: Instant) -> bool {
         // Cannot request available data on a socket that is closed by the
         // module
         if !self.is_connected() {
             return false;
         }
 
-        let ts = Instant::now();
+        // The modem already told us how much data is pending
+        if self.available_data_pushed {
+            return false;
+        }
 
         let should_update = self
             .last_check_time
-            .and_then(|last_check_time| ts.checked_duration_since(last_check_time))
+            .and_then(|last_check_time| now.checked_duration_since(last_check_time))
             .map(|dur| dur >= self.check_interval)
             .unwrap_or(true);
 
         if should_update {
-            self.last_check_time.replace(ts);
+            self.last_check_time.replace(now);
         }
 
         should_update
     }
 
+    /// Return the time elapsed since the available data was last checked, or
+    /// `None` if it has never been checked.
+    pub fn time_since_last_check(&self) -> Option<Duration> {
+        self.time_since_last_check_at(Instant::now())
+    }
+
+    /// Same as [time_since_last_check](#method.time_since_last_check), but with
+    /// `now` as the current time.
+    pub fn time_since_last_check_at(&self, now: Instant) -> Option<Duration> {
+        self.last_check_time
+            .and_then(|last_check_time| now.checked_duration_since(last_check_time))
+    }
+
+    /// Set the keep-alive interval, or `None` to disable keep-alives (the default).
+    ///
+    /// This is independent of the available data check interval, see
+    /// [needs_keepalive](#method.needs_keepalive).
+    pub fn set_keepalive(&mut self, keepalive: Option<Duration>) {
+        self.keepalive = keepalive;
+    }
+
+    /// Return the keep-alive interval.
+    pub fn keepalive(&self) -> Option<Duration> {
+        self.keepalive
+    }
+
+    /// Record that data was sent on the socket, restarting the keep-alive
+    /// interval.
+    ///
+    /// Connecting and receiving data are recorded as activity by the socket
+    /// itself.
+    pub fn record_activity(&mut self) {
+        self.record_activity_at(Instant::now())
+    }
+
+    /// Same as [record_activity](#method.record_activity), but with `now` as
+    /// the time of the activity.
+    pub fn record_activity_at(&mut self, now: Instant) {
+        self.last_activity.replace(now);
+        self.rx_activity = false;
+    }
+
+    /// Record data received since the last activity as activity at `now`.
+    ///
+    /// The receive path only flags the activity, so enqueueing does not need
+    /// to read the clock.
+    fn update_activity(&mut self, now: Instant) {
+        if core::mem::take(&mut self.rx_activity) {
+            self.last_activity.replace(now);
+        }
+    }
+
+    /// Return the time of the last activity, counting data received since
+    /// then as activity at `now`.
+    fn last_activity_at(&self, now: Instant) -> Option<Instant> {
+        if self.rx_activity {
+            Some(now)
+        } else {
+            self.last_activity
+        }
+    }
+
+    /// Return whether a connected socket has been idle for the keep-alive
+    /// interval, and the driver should send a keep-alive probe.
+    ///
+    /// The interval is counted from the last activity, i.e. connecting,
+    /// receiving data, or [recorded activity](#method.record_activity). Data
+    /// received since the last call counts as activity at the time of this
+    /// call.
+    pub fn needs_keepalive(&mut self) -> bool {
+        self.needs_keepalive_at(Instant::now())
+    }
+
+    /// Same as [needs_keepalive](#method.needs_keepalive), but with `now` as
+    /// the current time.
+    pub fn needs_keepalive_at(&mut self, now: Instant) -> bool {
+        self.update_activity(now);
+        if !self.is_connected() {
+            return false;
+        }
+
+        match (self.keepalive, self.last_activity) {
+            (Some(keepalive), Some(last_activity)) => now
+                .checked_duration_since(last_activity)
+                .map(|dur| dur >= keepalive)
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Return the earliest time this socket needs attention, and why, with
+    /// `now` as the current time.
+    ///
+    /// A socket whose available data has never been checked is due at `now`.
+    pub fn next_deadline_at(&self, now: Instant) -> Option<(Instant, PollReason)> {
+        let data_poll = (self.is_connected() && !self.available_data_pushed).then(|| {
+            let at = self
+                .last_check_time
+                .map_or(now, |last_check_time| last_check_time + self.check_interval);
+            (at, PollReason::DataPoll)
+        });
+
+        let recycle = match (&self.state, self.read_timeout) {
+            (State::ShutdownForWrite(closed_time), Some(read_timeout)) => {
+                Some((*closed_time + read_timeout, PollReason::RecycleDue))
+            }
+            _ => None,
+        };
+
+        let keepalive = match (
+            self.is_connected(),
+            self.keepalive,
+            self.last_activity_at(now),
+        ) {
+            (true, Some(keepalive), Some(last_activity)) => {
+                Some((last_activity + keepalive, PollReason::Keepalive))
+            }
+            _ => None,
+        };
+
+        [data_poll, recycle, keepalive]
+            .into_iter()
+            .flatten()
+            .min_by_key(|&(at, _)| at)
+    }
+
+    /// Set the idle timeout, or `None` to never close idle connections (the
+    /// default).
+    ///
+    /// This is independent of the keep-alive interval, see
+    /// [should_close_idle](#method.should_close_idle).
+    pub fn set_idle_timeout(&mut self, idle_timeout: Option<Duration>) {
+        self.idle_timeout = idle_timeout;
+    }
+
+    /// Return the idle timeout.
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// Return whether a connected socket has been idle for the idle timeout,
+    /// and the driver should close it to reclaim its resources.
+    ///
+    /// Like [needs_keepalive](#method.needs_keepalive), idleness is counted
+    /// from the last activity, i.e. connecting, receiving data, or
+    /// [recorded activity](#method.record_activity).
+    pub fn should_close_idle(&mut self) -> bool {
+        self.should_close_idle_at(Instant::now())
+    }
+
+    /// Same as [should_close_idle](#method.should_close_idle), but with `now`
+    /// as the current time.
+    pub fn should_close_idle_at(&mut self, now: Instant) -> bool {
+        self.update_activity(now);
+        if !self.is_connected() {
+            return false;
+        }
+
+        match (self.idle_timeout, self.last_activity) {
+            (Some(idle_timeout), Some(last_activity)) => now
+                .checked_duration_since(last_activity)
+                .map(|dur| dur >= idle_timeout)
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
     pub fn recycle(&self) -> bool {
+        self.recycle_at(Instant::now())
+    }
+
+    /// Same as [recycle](#method.recycle), but with `now` as the current time.
+    pub fn recycle_at(&self, now: Instant) -> bool {
         if let Some(read_timeout) = self.read_timeout {
             match self.state {
                 State::Created | State::WaitingForConnect(_) | State::Connected(_) => false,
-                State::ShutdownForWrite(closed_time) => Instant::now()
+                State::ShutdownForWrite(closed_time) => now
                     .checked_duration_since(closed_time)
                     .map(|dur| dur >= read_timeout)
                     .unwrap_or(false),
@@ -134,13 +631,55 @@ impl<const L: usize> TcpSocket<L> {
     }
 
     pub fn closed_by_remote(&mut self) {
-        self.set_state(State::ShutdownForWrite(Instant::now()));
+        self.closed_by_remote_at(Instant::now())
+    }
+
+    /// Same as [closed_by_remote](#method.closed_by_remote), but with `now` as
+    /// the time of closing.
+    pub fn closed_by_remote_at(&mut self, now: Instant) {
+        self.set_state_at(State::ShutdownForWrite(now), now);
         self.set_available_data(0);
+        self.close_reason = Some(CloseReason::RemoteReset);
+    }
+
+    /// Return why the connection was last closed, or `None` if it has not
+    /// been closed since it last started connecting.
+    ///
+    /// It is kept across [reset](#method.reset), so it can be reported after
+    /// the socket has been cleaned up.
+    ///
+    /// Moving the socket to [State::Created] with [set_state](#method.set_state)
+    /// after a close does not record a reason by itself, see
+    /// [set_close_reason](#method.set_close_reason).
+    pub fn close_reason(&self) -> Option<CloseReason> {
+        self.close_reason
+    }
+
+    /// Record why the connection was closed, for closes the socket cannot
+    /// observe itself, e.g. a local close or a modem error.
+    ///
+    /// [closed_by_remote](#method.closed_by_remote) records
+    /// [CloseReason::RemoteReset] by itself.
+    pub fn set_close_reason(&mut self, reason: CloseReason) {
+        self.close_reason = Some(reason);
     }
 
     /// Set available data.
     pub fn set_available_data(&mut self, available_data: usize) {
+        if available_data == 0 {
+            self.available_data_pushed = false;
+        }
+        self.available_data = available_data;
+    }
+
+    /// Set available data, as pushed by the modem through a URC.
+    ///
+    /// Until the pushed data has been consumed (available data is set back to
+    /// zero), [should_update_available_data](#method.should_update_available_data)
+    /// returns `false`, as polling the modem for the count would be redundant.
+    pub fn mark_data_available(&mut self, available_data: usize) {
         self.available_data = available_data;
+        self.available_data_pushed = available_data > 0;
     }
 
     /// Get the number of bytes available to ingress.
@@ -148,6 +687,21 @@ impl<const L: usize> TcpSocket<L> {
         self.available_data
     }
 
+    /// Subtract `n` octets read from the modem from the available data,
+    /// saturating at zero, so it keeps reflecting what is still left to read.
+    ///
+    /// The driver should call this with the amount actually enqueued, which
+    /// may be less than it requested if the receive buffer ran full.
+    pub fn consume_available(&mut self, n: usize) {
+        self.set_available_data(self.available_data.saturating_sub(n));
+    }
+
+    /// Return the amount of octets that can eventually be read, i.e. the
+    /// octets queued in the receive buffer and those still held by the modem.
+    pub fn total_readable(&self) -> usize {
+        self.recv_queue().saturating_add(self.available_data)
+    }
+
     /// Return whether a connection is active.
     ///
     /// This function returns true if the socket is actively exchanging packets
@@ -159,6 +713,36 @@ impl<const L: usize> TcpSocket<L> {
         matches!(self.state, State::Connected(_))
     }
 
+    /// Return whether the socket has ever been connected since it was created
+    /// or last [reset](#method.reset).
+    ///
+    /// This distinguishes a fresh socket in the `Created` state from one that
+    /// was returned to it after use.
+    pub fn was_connected(&self) -> bool {
+        self.was_connected
+    }
+
+    /// Return the amount of connection attempts since the socket was last
+    /// connected, i.e. the times it entered the `WaitingForConnect` state.
+    ///
+    /// It is kept across [reset](#method.reset), so it keeps counting while
+    /// the socket is reset between failed attempts.
+    pub fn connect_attempts(&self) -> u8 {
+        self.connect_attempts
+    }
+
+    /// Return how long to wait before the next connection attempt, backing
+    /// off exponentially with the amount of [connect_attempts], up to a
+    /// maximum of 64 seconds.
+    ///
+    /// [connect_attempts]: #method.connect_attempts
+    pub fn backoff_hint(&self) -> Duration {
+        match self.connect_attempts {
+            0 => Duration::from_secs(0),
+            attempts => min(BACKOFF_BASE * (1 << min(attempts - 1, 16)), BACKOFF_MAX),
+        }
+    }
+
     /// Return whether the receive half of the full-duplex connection is open.
     ///
     /// This function returns true if it's possible to receive data from the remote endpoint.
@@ -176,6 +760,32 @@ impl<const L: usize> TcpSocket<L> {
         }
     }
 
+    /// Return whether the receive half of the connection has been closed, i.e.
+    /// the remote closed the connection, or the socket was connected before
+    /// and is back in [State::Created].
+    ///
+    /// Unlike [may_recv](#method.may_recv), this ignores the receive buffer,
+    /// and is false for a socket that has not been connected yet, or is
+    /// connecting again.
+    fn rx_closed(&self) -> bool {
+        match self.state {
+            State::ShutdownForWrite(_) => true,
+            State::Created => self.was_connected,
+            State::WaitingForConnect(_) | State::Connected(_) => false,
+        }
+    }
+
+    /// Return whether the end of the stream has been reached, i.e. the remote
+    /// closed the connection, or the socket was connected before and is back
+    /// in [State::Created], and the receive buffer has been drained.
+    ///
+    /// No more data will ever be received once this returns true, which is
+    /// the condition a reader should report as end of file. A socket that has
+    /// not been connected yet has not reached the end of the stream.
+    pub fn eof(&self) -> bool {
+        self.rx_closed() && self.rx_buffer.is_empty()
+    }
+
     /// Check whether the receive half of the full-duplex connection buffer is open
     /// (see [may_recv](#method.may_recv), and the receive buffer is not full.
     pub fn can_recv(&self) -> bool {
@@ -183,29 +793,44 @@ impl<const L: usize> TcpSocket<L> {
             return false;
         }
 
-        !self.rx_buffer.is_full()
+        self.rx_window() != 0
     }
 
     fn recv_impl<'b, F, R>(&'b mut self, f: F) -> Result<R>
     where
         F: FnOnce(&'b mut SocketBuffer<L>) -> (usize, R),
     {
+        self.check_may_recv()?;
+
+        let (_size, result) = f(&mut self.rx_buffer);
+        Ok(result)
+    }
+
+    /// Return `Err(Error::SocketClosed)` if the end of the stream has been
+    /// reached (see [eof](#method.eof)), as no more data will be received.
+    ///
+    /// Otherwise, return `Ok(())` if the receive half of the connection is
+    /// open (see [may_recv](#method.may_recv)), or `Err(Error::Illegal)` if
+    /// the socket is not connected yet.
+    fn check_may_recv(&self) -> Result<()> {
         // We may have received some data inside the initial SYN, but until the connection
         // is fully open we must not dequeue any data, as it may be overwritten by e.g.
         // another (stale) SYN. (We do not support TCP Fast Open.)
-        if !self.may_recv() {
-            return Err(Error::Illegal);
+        if self.eof() {
+            Err(Error::SocketClosed)
+        } else if self.may_recv() {
+            Ok(())
+        } else {
+            Err(Error::Illegal)
         }
-
-        let (_size, result) = f(&mut self.rx_buffer);
-        Ok(result)
     }
 
     /// Call `f` with the largest contiguous slice of octets in the receive buffer,
     /// and dequeue the amount of elements returned by `f`.
     ///
-    /// This function returns `Err(Error::Illegal) if the receive half of
-    /// the connection is not open; see [may_recv](#method.may_recv).
+    /// This function returns `Err(Error::SocketClosed)` if the connection has
+    /// been closed and the receive buffer drained, or `Err(Error::Illegal)` if
+    /// the socket is not connected yet; see [may_recv](#method.may_recv).
     pub fn recv<'b, F, R>(&'b mut self, f: F) -> Result<R>
     where
         F: FnOnce(&'b mut [u8]) -> (usize, R),
@@ -220,8 +845,9 @@ impl<const L: usize> TcpSocket<L> {
     /// `Some()` with the remainder of the buffer, such that the combined slice
     /// of the two arguments, makes up the full buffer.
     ///
-    /// This function returns `Err(Error::Illegal) if the receive half of the
-    /// connection is not open; see [may_recv](#method.may_recv).
+    /// This function returns `Err(Error::SocketClosed)` if the connection has
+    /// been closed and the receive buffer drained, or `Err(Error::Illegal)` if
+    /// the socket is not connected yet; see [may_recv](#method.may_recv).
     pub fn recv_wrapping<'b, F>(&'b mut self, f: F) -> Result<usize>
     where
         F: FnOnce(&'b [u8], Option<&'b [u8]>) -> usize,
@@ -234,13 +860,43 @@ impl<const L: usize> TcpSocket<L> {
         })
     }
 
+    /// Call `f` with a slice of octets in the receive buffer, without
+    /// removing them from the receive buffer.
+    ///
+    /// If the buffer wraps around, the second argument of `f` will be `Some()`
+    /// with the remainder of the buffer, such that the combined slice of the
+    /// two arguments, makes up the full buffer.
+    ///
+    /// This function otherwise behaves identically to [recv_wrapping](#method.recv_wrapping).
+    pub fn peek_wrapping<F>(&self, f: F) -> Result<usize>
+    where
+        F: FnOnce(&[u8], Option<&[u8]>) -> usize,
+    {
+        self.check_may_recv()?;
+
+        let len = self.rx_buffer.len();
+        let first = self.rx_buffer.get_allocated(0, len);
+        let second = self.rx_buffer.get_allocated(first.len(), len - first.len());
+        if second.is_empty() {
+            Ok(f(first, None))
+        } else {
+            Ok(f(first, Some(second)))
+        }
+    }
+
     /// Dequeue a sequence of received octets, and fill a slice from it.
     ///
     /// This function returns the amount of bytes actually dequeued, which is limited
     /// by the amount of free space in the transmit buffer; down to zero.
     ///
     /// See also [recv](#method.recv).
+    ///
+    /// An empty `data` returns `Ok(0)` in any state, without side effects.
     pub fn recv_slice(&mut self, data: &mut [u8]) -> Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+
         self.recv_impl(|rx_buffer| {
             let size = rx_buffer.dequeue_slice(data);
             (size, size)
@@ -251,17 +907,119 @@ impl<const L: usize> TcpSocket<L> {
     /// the receive buffer, and return a pointer to it.
     ///
     /// This function otherwise behaves identically to [recv](#method.recv).
+    ///
+    /// A `size` of zero returns an empty slice in any state.
     pub fn peek(&mut self, size: usize) -> Result<&[u8]> {
-        // See recv() above.
-        if !self.may_recv() {
-            return Err(Error::Illegal);
+        if size == 0 {
+            return Ok(&[]);
         }
 
+        self.check_may_recv()?;
+
         Ok(self.rx_buffer.get_allocated(0, size))
     }
 
+    /// Dequeue a single received octet, or return `Ok(None)` if the receive
+    /// buffer is empty.
+    ///
+    /// This function otherwise behaves identically to [recv](#method.recv).
+    pub fn read_byte(&mut self) -> Result<Option<u8>> {
+        self.recv_impl(|rx_buffer| match rx_buffer.dequeue_one() {
+            Ok(octet) => (1, Some(*octet)),
+            Err(_) => (0, None),
+        })
+    }
+
+    /// Peek at a single received octet without removing it from the receive
+    /// buffer, or return `Ok(None)` if the receive buffer is empty.
+    ///
+    /// This function otherwise behaves identically to [recv](#method.recv).
+    pub fn peek_byte(&self) -> Result<Option<u8>> {
+        self.check_may_recv()?;
+
+        Ok(self.rx_buffer.peek_byte())
+    }
+
+    /// Return the amount of octets that can be enqueued into the receive
+    /// buffer, limited by the receive cap, or zero while the socket is
+    /// [paused](#method.pause).
     pub fn rx_window(&self) -> usize {
-        self.rx_buffer.window()
+        if self.paused {
+            return 0;
+        }
+
+        min(
+            self.rx_buffer.window(),
+            self.rx_cap.saturating_sub(self.rx_buffer.len()),
+        )
+    }
+
+    /// Pause receiving, so the driver stops reading from the modem for this
+    /// socket, e.g. while the application flushes received data to flash.
+    ///
+    /// While paused, [can_recv](#method.can_recv) returns `false` and the
+    /// [receive window](#method.rx_window) is zero, regardless of buffer space.
+    /// The socket stays paused until [resume](#method.resume) or
+    /// [reset](#method.reset) is called.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resume receiving after [pause](#method.pause).
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Return whether receiving is paused, see [pause](#method.pause).
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Return the maximum amount of octets the receive buffer will hold.
+    pub fn rx_cap(&self) -> usize {
+        self.rx_cap
+    }
+
+    /// Limit the amount of octets the receive buffer will hold, similar to
+    /// `SO_RCVBUF`, to leave headroom for other sockets. Enqueueing beyond the
+    /// cap is rejected, even if there is space left in the receive buffer.
+    ///
+    /// The cap is clamped to the receive buffer capacity, which is the default.
+    pub fn set_rx_cap(&mut self, cap: usize) {
+        self.rx_cap = min(cap, L);
+    }
+
+    /// Return the receive buffer watermarks.
+    pub fn watermarks(&self) -> Watermarks {
+        self.watermarks
+    }
+
+    /// Set the receive buffer watermarks used by
+    /// [should_throttle](#method.should_throttle) and
+    /// [should_resume](#method.should_resume).
+    ///
+    /// Defaults to [Watermarks::for_capacity] of the receive buffer. This
+    /// function returns `Err(Error::Illegal)` if `low` is larger than `high`,
+    /// or `high` is larger than the receive buffer capacity.
+    pub fn set_watermarks(&mut self, watermarks: Watermarks) -> Result<()> {
+        if watermarks.low > watermarks.high || watermarks.high > L {
+            return Err(Error::Illegal);
+        }
+
+        self.watermarks = watermarks;
+        Ok(())
+    }
+
+    /// Return whether the receive buffer is filled above the high watermark,
+    /// and reading from the modem should be paused.
+    pub fn should_throttle(&self) -> bool {
+        self.rx_buffer.len() > self.watermarks.high
+    }
+
+    /// Return whether the receive buffer is drained below the low watermark,
+    /// and reading from the modem can be resumed.
+    pub fn should_resume(&self) -> bool {
+        self.rx_buffer.len() < self.watermarks.low
     }
 
     /// Peek at a sequence of received octets without removing them from
@@ -269,14 +1027,173 @@ impl<const L: usize> TcpSocket<L> {
     ///
     /// This function otherwise behaves identically to [recv_slice](#method.recv_slice).
     pub fn peek_slice(&mut self, data: &mut [u8]) -> Result<usize> {
-        let buffer = self.peek(data.len())?;
-        let data = &mut data[..buffer.len()];
-        data.copy_from_slice(buffer);
-        Ok(buffer.len())
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        self.check_may_recv()?;
+
+        Ok(self.rx_buffer.peek_into(data))
+    }
+
+    /// Dequeue received octets into `data` like [recv_slice](#method.recv_slice),
+    /// but return `Err(Error::Timeout)` if the receive buffer is empty and
+    /// `deadline` has passed.
+    pub fn recv_deadline(&mut self, data: &mut [u8], deadline: Instant) -> Result<usize> {
+        self.recv_deadline_at(data, deadline, Instant::now())
+    }
+
+    /// Same as [recv_deadline](#method.recv_deadline), but with `now` as the
+    /// current time.
+    pub fn recv_deadline_at(
+        &mut self,
+        data: &mut [u8],
+        deadline: Instant,
+        now: Instant,
+    ) -> Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        let size = self.recv_slice(data)?;
+        if size == 0 && self.rx_buffer.is_empty() && now >= deadline {
+            return Err(Error::Timeout);
+        }
+        Ok(size)
     }
 
     pub fn rx_enqueue_slice(&mut self, data: &[u8]) -> usize {
-        self.rx_buffer.enqueue_slice(data)
+        let was_empty = self.rx_buffer.is_empty();
+        let len = min(data.len(), self.rx_window());
+        let len = self.rx_buffer.enqueue_slice(&data[..len]);
+        self.rx_enqueued(was_empty, &data[..len]);
+        len
+    }
+
+    /// Enqueue all of `data` into the receive buffer.
+    ///
+    /// Unlike [rx_enqueue_slice](#method.rx_enqueue_slice), this never
+    /// truncates. If `data` does not fit in the receive buffer, nothing is
+    /// enqueued, and `Err(Error::Exhausted)` is returned.
+    pub fn try_rx_enqueue_slice(&mut self, data: &[u8]) -> Result<()> {
+        if data.len() > self.rx_window() {
+            return Err(Error::Exhausted);
+        }
+
+        let was_empty = self.rx_buffer.is_empty();
+        self.rx_buffer.enqueue_slice(data);
+        self.rx_enqueued(was_empty, data);
+        Ok(())
+    }
+
+    /// Return the CRC-32 (IEEE 802.3) of all octets enqueued into the receive
+    /// buffer since the socket was created, or last [reset](#method.reset) or
+    /// [reset_rx_crc](#method.reset_rx_crc), e.g. to verify a download
+    /// without a separate pass over the data.
+    ///
+    /// It is updated as octets are enqueued, so it covers octets that have
+    /// since been received.
+    pub fn rx_running_crc(&self) -> u32 {
+        self.rx_crc
+    }
+
+    /// Restart the [running CRC](#method.rx_running_crc) of received octets.
+    pub fn reset_rx_crc(&mut self) {
+        self.rx_crc = 0;
+    }
+
+    /// Return the stream offset the next data enqueued with
+    /// [rx_enqueue_slice_at](#method.rx_enqueue_slice_at) must start at, if
+    /// it is being tracked.
+    pub fn expected_offset(&self) -> Option<u32> {
+        self.expected_offset
+    }
+
+    /// Start tracking the stream offset of received data at `offset`, or stop
+    /// tracking it with `None` (the default).
+    pub fn set_expected_offset(&mut self, offset: Option<u32>) {
+        self.expected_offset = offset;
+    }
+
+    /// Enqueue data read from the modem at the stream offset `offset` into
+    /// the receive buffer, like [rx_enqueue_slice](#method.rx_enqueue_slice),
+    /// and return the amount of octets enqueued.
+    ///
+    /// While the [expected offset](#method.set_expected_offset) is tracked,
+    /// data at any other offset, e.g. re-delivered by the modem after a
+    /// retry, is rejected with `Err(Error::Illegal)`, and the expected offset
+    /// is advanced by the amount of octets enqueued.
+    pub fn rx_enqueue_slice_at(&mut self, data: &[u8], offset: u32) -> Result<usize> {
+        match self.expected_offset {
+            Some(expected) if expected != offset => Err(Error::Illegal),
+            Some(expected) => {
+                let len = self.rx_enqueue_slice(data);
+                self.expected_offset = Some(expected.wrapping_add(len as u32));
+                Ok(len)
+            }
+            None => Ok(self.rx_enqueue_slice(data)),
+        }
+    }
+
+    /// Return the largest amount of octets ever queued in the receive buffer,
+    /// to help decide whether the buffer size `L` is over- or under-provisioned.
+    ///
+    /// It is kept across [reset](#method.reset), and only cleared by
+    /// [reset_high_water_mark](#method.reset_high_water_mark).
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
+    /// Restart tracking the largest amount of octets queued in the receive
+    /// buffer, from the amount currently queued.
+    pub fn reset_high_water_mark(&mut self) {
+        self.high_water_mark = self.rx_buffer.len();
+    }
+
+    /// Update the receive buffer bookkeeping after enqueueing `data` into it,
+    /// given whether it was empty before.
+    fn rx_enqueued(&mut self, was_empty: bool, data: &[u8]) {
+        self.high_water_mark = self.high_water_mark.max(self.rx_buffer.len());
+        self.rx_crc = crc32_update(self.rx_crc, data);
+        if !data.is_empty() {
+            self.rx_activity = true;
+        }
+
+        #[cfg(feature = "async")]
+        if was_empty && !self.rx_buffer.is_empty() {
+            if let Some(waker) = self.rx_waker.take() {
+                waker.wake();
+            }
+        }
+        #[cfg(not(feature = "async"))]
+        let _ = was_empty;
+    }
+
+    /// Register `waker` to be woken when data is enqueued into the empty
+    /// receive buffer, e.g. by a future waiting to read from the socket.
+    ///
+    /// Only a single waker is stored, replacing any waker registered before.
+    /// It is woken at most once, and must be registered again afterwards.
+    #[cfg(feature = "async")]
+    pub fn register_rx_waker(&mut self, waker: &Waker) {
+        match &self.rx_waker {
+            Some(registered) if registered.will_wake(waker) => {}
+            _ => self.rx_waker = Some(waker.clone()),
+        }
+    }
+
+    /// Drain the receive buffer into `data`, and return the amount of octets
+    /// moved.
+    ///
+    /// Unlike [recv_slice](#method.recv_slice), this does not depend on the
+    /// socket state. If `data` is shorter than the amount of octets queued,
+    /// the remaining octets are left in the receive buffer.
+    pub fn take_rx(&mut self, data: &mut [u8]) -> usize {
+        self.rx_buffer.dequeue_slice(data)
+    }
+
+    pub(crate) fn rx_buffer(&self) -> &SocketBuffer<L> {
+        &self.rx_buffer
     }
 
     /// Return the amount of octets queued in the receive buffer.
@@ -286,15 +1203,135 @@ impl<const L: usize> TcpSocket<L> {
         self.rx_buffer.len()
     }
 
+    /// Return whether octets can be received right away, or if not, whether
+    /// more may arrive.
+    ///
+    /// A socket that has not been connected yet, or is connecting again,
+    /// reports [RecvStatus::WouldBlock], as data may still arrive once it
+    /// connects.
+    ///
+    /// This does not modify the receive buffer.
+    pub fn recv_status(&self) -> RecvStatus {
+        match self.rx_buffer.len() {
+            0 if self.rx_closed() => RecvStatus::Closed,
+            0 => RecvStatus::WouldBlock,
+            len => RecvStatus::Data(len),
+        }
+    }
+
+    /// Write a compact ASCII summary of the socket into `out`, for logging,
+    /// and return the amount of octets written.
+    ///
+    /// The summary holds the handle, state, receive queue length, available
+    /// data, and the first few buffered octets in hex, e.g.
+    /// `h=1 s=connected rx=3 avail=0 data=616263`. It is truncated to fit `out`.
+    pub fn debug_dump(&self, out: &mut [u8]) -> usize {
+        let state = match self.state {
+            State::Created => "created",
+            State::WaitingForConnect(_) => "connecting",
+            State::Connected(_) => "connected",
+            State::ShutdownForWrite(_) => "closed",
+        };
+        let (first, second) = self.rx_buffer.as_slices();
+
+        let mut writer = SliceWriter { buf: out, len: 0 };
+        write!(
+            writer,
+            "h={} s={} rx={} avail={} data=",
+            self.handle(),
+            state,
+            self.recv_queue(),
+            self.available_data
+        )
+        .and_then(|_| {
+            first
+                .iter()
+                .chain(second)
+                .take(DEBUG_DUMP_OCTETS)
+                .try_for_each(|octet| write!(writer, "{:02x}", octet))
+        })
+        .ok();
+        writer.len
+    }
+
+    /// Move the socket to `state`, if it is a legal transition from the
+    /// current state, or return `Err(Error::Illegal)` otherwise.
+    ///
+    /// The legal transitions are:
+    ///
+    /// ```text
+    /// Created -> WaitingForConnect -> Connected -> ShutdownForWrite
+    ///    ^                                                |
+    ///    +------------------------------------------------+
+    /// ```
+    ///
+    /// Additionally, any state may return to `Created`, e.g. when a connection
+    /// attempt fails or the socket is aborted.
+    pub fn transition(&mut self, state: State) -> Result<()> {
+        self.transition_at(state, Instant::now())
+    }
+
+    /// Same as [transition](#method.transition), but with `now` as the time
+    /// of the transition.
+    pub fn transition_at(&mut self, state: State, now: Instant) -> Result<()> {
+        let legal = matches!(
+            (&self.state, &state),
+            (_, State::Created)
+                | (State::Created, State::WaitingForConnect(_))
+                | (State::WaitingForConnect(_), State::Connected(_))
+                | (State::Connected(_), State::ShutdownForWrite(_))
+        );
+
+        if !legal {
+            return Err(Error::Illegal);
+        }
+
+        self.set_state_at(state, now);
+        Ok(())
+    }
+
+    /// Move the socket to `state`, without validating the transition.
+    ///
+    /// See [transition](#method.transition) for a validating alternative.
     pub fn set_state(&mut self, state: State) {
+        self.set_state_at(state, Instant::now())
+    }
+
+    /// Same as [set_state](#method.set_state), but with `now` as the time of
+    /// the transition.
+    pub fn set_state_at(&mut self, state: State, now: Instant) {
         debug!(
             "[TCP Socket] [{:?}] state change: {:?} -> {:?}",
             self.handle(),
             self.state,
             state
         );
+        match state {
+            State::WaitingForConnect(_) => {
+                self.connect_attempts = self.connect_attempts.saturating_add(1);
+                self.close_reason = None;
+            }
+            State::Connected(_) => {
+                self.was_connected = true;
+                self.connect_attempts = 0;
+                self.record_activity_at(now);
+            }
+            _ => {}
+        }
+        #[cfg(feature = "trace-history")]
+        self.state_history.write((StateKind::from(&state), now));
         self.state = state
     }
+
+    /// Return the last [STATE_HISTORY_LEN] state transitions, and the time of
+    /// each, to inspect the sequence of events leading up to a misbehaving
+    /// connection.
+    ///
+    /// The history is kept across [reset](#method.reset).
+    #[cfg(feature = "trace-history")]
+    pub fn state_history(&self) -> &HistoryBuffer<(StateKind, Instant), STATE_HISTORY_LEN> {
+        &self.state_history
+    }
 }
 
 impl<const L: usize> From<TcpSocket<L>> for Socket<L> {
@@ -302,3 +1339,718 @@ impl<const L: usize> From<TcpSocket<L>> for Socket<L> {
         Socket::Tcp(val)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use no_std_net::{Ipv4Addr, SocketAddrV4};
+
+    fn connected_socket() -> TcpSocket<64> {
+        let mut socket = TcpSocket::new(0);
+        socket.set_state(State::Connected(SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::new(127, 0, 0, 1),
+            80,
+        ))));
+        socket
+    }
+
+    #[test]
+    fn const_new() {
+        static SOCKET: TcpSocket<8> = TcpSocket::new(3);
+        assert_eq!(SOCKET.handle(), SocketHandle(3));
+        assert_eq!(SOCKET.state(), &State::Created);
+    }
+
+    #[test]
+    fn should_update_available_data() {
+        let start = Instant::from_secs(100);
+        let mut socket = TcpSocket::<64>::new(0);
+        assert!(!socket.should_update_available_data_at(start));
+
+        let mut socket = connected_socket();
+        assert!(socket.should_update_available_data_at(start));
+        assert!(!socket.should_update_available_data_at(start + Duration::from_secs(14)));
+        assert!(socket.should_update_available_data_at(start + Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn time_since_last_check() {
+        let start = Instant::from_secs(100);
+        let mut socket = connected_socket();
+        assert_eq!(socket.time_since_last_check_at(start), None);
+
+        assert!(socket.should_update_available_data_at(start));
+        assert_eq!(
+            socket.time_since_last_check_at(start + Duration::from_secs(3)),
+            Some(Duration::from_secs(3))
+        );
+    }
+
+    #[test]
+    fn mark_data_available() {
+        let start = Instant::from_secs(100);
+        let mut socket = connected_socket();
+        assert!(socket.should_update_available_data_at(start));
+
+        socket.mark_data_available(10);
+        assert!(!socket.should_update_available_data_at(start + Duration::from_secs(30)));
+
+        socket.set_available_data(0);
+        assert!(socket.should_update_available_data_at(start + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn token() {
+        let mut socket = TcpSocket::<64>::new(0);
+        assert_eq!(socket.token(), 0);
+
+        socket.set_token(7);
+        assert_eq!(socket.token(), 7);
+
+        socket.reset();
+        assert_eq!(socket.token(), 0);
+    }
+
+    #[test]
+    fn total_readable() {
+        let mut socket = connected_socket();
+        assert_eq!(socket.total_readable(), 0);
+
+        assert_eq!(socket.rx_enqueue_slice(b"hello"), 5);
+        socket.set_available_data(10);
+        assert_eq!(socket.total_readable(), 15);
+
+        // The sum saturates instead of overflowing
+        socket.set_available_data(usize::MAX);
+        assert_eq!(socket.total_readable(), usize::MAX);
+    }
+
+    #[test]
+    fn watermarks() {
+        let mut socket = TcpSocket::<64>::new(0);
+        assert_eq!(socket.watermarks(), Watermarks { low: 16, high: 48 });
+
+        assert_eq!(
+            socket.set_watermarks(Watermarks { low: 32, high: 16 }),
+            Err(Error::Illegal)
+        );
+        assert_eq!(
+            socket.set_watermarks(Watermarks { low: 16, high: 65 }),
+            Err(Error::Illegal)
+        );
+        assert_eq!(
+            socket.set_watermarks(Watermarks { low: 8, high: 32 }),
+            Ok(())
+        );
+
+        assert!(socket.should_resume());
+        assert_eq!(socket.rx_enqueue_slice(&[0; 33]), 33);
+        assert!(socket.should_throttle());
+        assert!(!socket.should_resume());
+    }
+
+    #[test]
+    fn try_rx_enqueue_slice() {
+        let mut socket = TcpSocket::<8>::new(0);
+        assert_eq!(socket.try_rx_enqueue_slice(b"hello"), Ok(()));
+        assert_eq!(socket.try_rx_enqueue_slice(b"world"), Err(Error::Exhausted));
+        assert_eq!(socket.recv_queue(), 5);
+        assert_eq!(socket.try_rx_enqueue_slice(b"abc"), Ok(()));
+        assert_eq!(socket.recv_queue(), 8);
+    }
+
+    #[test]
+    fn was_connected() {
+        let socket = TcpSocket::<64>::new(0);
+        assert!(!socket.was_connected());
+
+        let mut socket = connected_socket();
+        socket.set_state(State::Created);
+        assert!(socket.was_connected());
+
+        socket.reset();
+        assert!(!socket.was_connected());
+    }
+
+    #[test]
+    fn zero_length_recv() {
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        for state in [
+            State::Created,
+            State::WaitingForConnect(remote),
+            State::Connected(remote),
+            State::ShutdownForWrite(Instant::from_secs(100)),
+        ] {
+            let mut socket = TcpSocket::<64>::new(0);
+            socket.set_state(state);
+            assert_eq!(socket.recv_slice(&mut []), Ok(0));
+            assert_eq!(socket.peek(0), Ok(&[][..]));
+            assert_eq!(
+                socket.recv_deadline_at(&mut [], Instant::from_secs(0), Instant::from_secs(100)),
+                Ok(0)
+            );
+        }
+
+        let mut socket = connected_socket();
+        assert_eq!(socket.rx_enqueue_slice(b"hello"), 5);
+        assert_eq!(socket.recv_slice(&mut []), Ok(0));
+        assert_eq!(socket.recv_queue(), 5);
+    }
+
+    #[test]
+    fn high_water_mark() {
+        let mut socket = connected_socket();
+        assert_eq!(socket.high_water_mark(), 0);
+
+        assert_eq!(socket.rx_enqueue_slice(b"hello"), 5);
+        assert_eq!(socket.try_rx_enqueue_slice(b"world"), Ok(()));
+        let mut data = [0; 8];
+        assert_eq!(socket.recv_slice(&mut data), Ok(8));
+        assert_eq!(socket.rx_enqueue_slice(b"abc"), 3);
+        assert_eq!(socket.high_water_mark(), 10);
+
+        socket.reset_high_water_mark();
+        assert_eq!(socket.high_water_mark(), 5);
+    }
+
+    #[test]
+    fn backoff_hint() {
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        let mut socket = TcpSocket::<64>::new(0);
+        assert_eq!(socket.backoff_hint(), Duration::from_secs(0));
+
+        for (attempts, backoff) in [(1, 1), (2, 2), (3, 4), (7, 64), (8, 64)] {
+            while socket.connect_attempts() < attempts {
+                socket.set_state(State::WaitingForConnect(remote));
+                socket.reset();
+            }
+            assert_eq!(socket.backoff_hint(), Duration::from_secs(backoff));
+        }
+
+        socket.set_state(State::WaitingForConnect(remote));
+        socket.set_state(State::Connected(remote));
+        assert_eq!(socket.connect_attempts(), 0);
+        assert_eq!(socket.backoff_hint(), Duration::from_secs(0));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn rx_waker() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::task::Wake;
+
+        struct CountingWaker(AtomicUsize);
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let count = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut socket = connected_socket();
+
+        socket.register_rx_waker(&waker);
+        assert_eq!(socket.rx_enqueue_slice(b"hello"), 5);
+        assert_eq!(count.0.load(Ordering::SeqCst), 1);
+
+        // Only woken on the transition from empty to non-empty
+        socket.register_rx_waker(&waker);
+        assert_eq!(socket.try_rx_enqueue_slice(b"world"), Ok(()));
+        assert_eq!(count.0.load(Ordering::SeqCst), 1);
+
+        assert_eq!(socket.take_rx(&mut [0; 16]), 10);
+        assert_eq!(socket.rx_enqueue_slice(b"again"), 5);
+        assert_eq!(count.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn take_rx() {
+        // Not connected, as draining does not depend on the socket state
+        let mut socket = TcpSocket::<8>::new(0);
+        assert_eq!(socket.rx_enqueue_slice(b"......ab"), 8);
+        assert_eq!(socket.take_rx(&mut [0; 6]), 6);
+        assert_eq!(socket.rx_enqueue_slice(b"cdef"), 4);
+
+        // Drains across the wrap point, leaving what does not fit in `data`
+        let mut data = [0; 5];
+        assert_eq!(socket.take_rx(&mut data), 5);
+        assert_eq!(&data, b"abcde");
+        assert_eq!(socket.recv_queue(), 1);
+    }
+
+    #[test]
+    fn eof() {
+        assert!(!TcpSocket::<64>::new(0).eof());
+
+        let mut socket = connected_socket();
+        assert!(!socket.eof());
+
+        assert_eq!(socket.rx_enqueue_slice(b"hello"), 5);
+        socket.closed_by_remote_at(Instant::from_secs(100));
+        assert!(!socket.eof());
+
+        let mut data = [0; 8];
+        assert_eq!(socket.recv_slice(&mut data), Ok(5));
+        assert!(socket.eof());
+    }
+
+    #[test]
+    fn recv_status() {
+        let socket = TcpSocket::<64>::new(0);
+        assert_eq!(socket.recv_status(), RecvStatus::WouldBlock);
+
+        let mut socket = connected_socket();
+        assert_eq!(socket.recv_status(), RecvStatus::WouldBlock);
+        assert_eq!(socket.rx_enqueue_slice(b"ab"), 2);
+        assert_eq!(socket.recv_status(), RecvStatus::Data(2));
+
+        // Buffered octets are reported before the close
+        socket.closed_by_remote_at(Instant::from_secs(100));
+        assert_eq!(socket.recv_status(), RecvStatus::Data(2));
+        assert_eq!(socket.recv_slice(&mut [0; 4]), Ok(2));
+        assert_eq!(socket.recv_status(), RecvStatus::Closed);
+
+        // A socket that was connected, but no longer is, is closed as well
+        let mut socket = connected_socket();
+        socket.set_state(State::Created);
+        assert_eq!(socket.recv_status(), RecvStatus::Closed);
+
+        // Until it connects again
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        assert_eq!(socket.transition(State::WaitingForConnect(remote)), Ok(()));
+        assert_eq!(socket.recv_status(), RecvStatus::WouldBlock);
+        assert_eq!(socket.transition(State::Connected(remote)), Ok(()));
+        assert_eq!(socket.recv_status(), RecvStatus::WouldBlock);
+    }
+
+    #[test]
+    fn last_error() {
+        let mut socket = connected_socket();
+        assert!(!socket.has_error());
+        assert_eq!(socket.take_last_error(), None);
+
+        socket.set_last_error(Error::Timeout);
+        socket.set_last_error(Error::SocketClosed);
+        assert!(socket.has_error());
+        assert_eq!(socket.take_last_error(), Some(Error::SocketClosed));
+        assert!(!socket.has_error());
+
+        socket.set_last_error(Error::Timeout);
+        socket.reset();
+        assert!(!socket.has_error());
+    }
+
+    #[test]
+    fn needs_keepalive() {
+        let start = Instant::from_secs(100);
+        let mut socket = connected_socket();
+        socket.record_activity_at(start);
+        assert!(!socket.needs_keepalive_at(start + Duration::from_secs(60)));
+
+        socket.set_keepalive(Some(Duration::from_secs(30)));
+        assert!(!socket.needs_keepalive_at(start + Duration::from_secs(29)));
+        assert!(socket.needs_keepalive_at(start + Duration::from_secs(30)));
+
+        socket.record_activity_at(start + Duration::from_secs(30));
+        assert!(!socket.needs_keepalive_at(start + Duration::from_secs(31)));
+    }
+
+    #[test]
+    fn keepalive_activity() {
+        let start = Instant::from_secs(100);
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        let mut socket = TcpSocket::<64>::new(0);
+        socket.set_keepalive(Some(Duration::from_secs(30)));
+
+        // Counted from the connect, without any recorded activity
+        socket.set_state_at(State::Connected(remote), start);
+        assert!(!socket.needs_keepalive_at(start + Duration::from_secs(29)));
+        assert!(socket.needs_keepalive_at(start + Duration::from_secs(30)));
+
+        // Received data counts as activity at the next check
+        assert_eq!(socket.rx_enqueue_slice(b"hello"), 5);
+        assert!(!socket.needs_keepalive_at(start + Duration::from_secs(31)));
+        assert!(!socket.needs_keepalive_at(start + Duration::from_secs(60)));
+        assert!(socket.needs_keepalive_at(start + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn next_deadline() {
+        let start = Instant::from_secs(100);
+        assert_eq!(TcpSocket::<64>::new(0).next_deadline_at(start), None);
+
+        let mut socket = connected_socket();
+        assert_eq!(
+            socket.next_deadline_at(start),
+            Some((start, PollReason::DataPoll))
+        );
+
+        assert!(socket.should_update_available_data_at(start));
+        socket.record_activity_at(start);
+        socket.set_keepalive(Some(Duration::from_secs(10)));
+        assert_eq!(
+            socket.next_deadline_at(start),
+            Some((start + Duration::from_secs(10), PollReason::Keepalive))
+        );
+
+        socket.closed_by_remote_at(start);
+        assert_eq!(
+            socket.next_deadline_at(start),
+            Some((start + Duration::from_secs(15), PollReason::RecycleDue))
+        );
+    }
+
+    #[test]
+    fn debug_dump() {
+        let mut socket = TcpSocket::<64>::new(1);
+        let mut out = [0; 64];
+        let len = socket.debug_dump(&mut out);
+        assert_eq!(&out[..len], b"h=1 s=created rx=0 avail=0 data=");
+
+        let mut socket = connected_socket();
+        socket.rx_enqueue_slice(b"abcdefghij");
+        socket.set_available_data(5);
+        let len = socket.debug_dump(&mut out);
+        assert_eq!(
+            &out[..len],
+            b"h=0 s=connected rx=10 avail=5 data=6162636465666768"
+        );
+
+        // The summary is truncated to fit
+        let mut out = [0; 8];
+        assert_eq!(socket.debug_dump(&mut out), 8);
+        assert_eq!(&out, b"h=0 s=co");
+    }
+
+    #[test]
+    fn read_byte() {
+        let mut socket = TcpSocket::<64>::new(0);
+        assert_eq!(socket.read_byte(), Err(Error::Illegal));
+        assert_eq!(socket.peek_byte(), Err(Error::Illegal));
+
+        let mut socket = connected_socket();
+        assert_eq!(socket.read_byte(), Ok(None));
+        socket.rx_enqueue_slice(b"ab");
+        assert_eq!(socket.peek_byte(), Ok(Some(b'a')));
+        assert_eq!(socket.read_byte(), Ok(Some(b'a')));
+        assert_eq!(socket.read_byte(), Ok(Some(b'b')));
+        assert_eq!(socket.peek_byte(), Ok(None));
+
+        // Buffered octets can still be read once the remote closed
+        socket.rx_enqueue_slice(b"c");
+        socket.closed_by_remote_at(Instant::from_secs(100));
+        assert_eq!(socket.read_byte(), Ok(Some(b'c')));
+        assert_eq!(socket.read_byte(), Err(Error::SocketClosed));
+    }
+
+    #[test]
+    fn with_rx_data() {
+        let mut socket = TcpSocket::<4>::with_rx_data(2, b"hello");
+        assert_eq!(socket.handle(), SocketHandle(2));
+        assert!(socket.is_connected());
+
+        let mut data = [0; 8];
+        assert_eq!(socket.recv_slice(&mut data), Ok(4));
+        assert_eq!(&data[..4], b"hell");
+    }
+
+    #[test]
+    fn pause() {
+        let mut socket = connected_socket();
+        assert!(socket.can_recv());
+
+        socket.pause();
+        assert!(socket.is_paused());
+        assert!(!socket.can_recv());
+        assert_eq!(socket.rx_window(), 0);
+        assert_eq!(socket.rx_enqueue_slice(b"abc"), 0);
+
+        socket.resume();
+        assert!(socket.can_recv());
+        assert_eq!(socket.rx_window(), 64);
+
+        socket.pause();
+        socket.reset();
+        assert!(!socket.is_paused());
+    }
+
+    #[test]
+    fn rx_enqueue_slice_at() {
+        let mut socket = connected_socket();
+        assert_eq!(socket.rx_enqueue_slice_at(b"abc", 7), Ok(3));
+
+        socket.set_expected_offset(Some(0));
+        assert_eq!(socket.rx_enqueue_slice_at(b"def", 0), Ok(3));
+        assert_eq!(socket.expected_offset(), Some(3));
+
+        // Re-delivered data is rejected
+        assert_eq!(socket.rx_enqueue_slice_at(b"def", 0), Err(Error::Illegal));
+        assert_eq!(socket.rx_enqueue_slice_at(b"ghi", 3), Ok(3));
+        assert_eq!(socket.recv_queue(), 9);
+
+        socket.reset();
+        assert_eq!(socket.expected_offset(), None);
+    }
+
+    #[test]
+    fn recv_closed() {
+        let mut data = [0; 4];
+        let mut socket = TcpSocket::<64>::new(0);
+        assert_eq!(socket.recv_slice(&mut data), Err(Error::Illegal));
+
+        let mut socket = connected_socket();
+        socket.rx_enqueue_slice(b"ab");
+        socket.closed_by_remote_at(Instant::from_secs(100));
+        assert_eq!(socket.recv_slice(&mut data), Ok(2));
+        assert_eq!(socket.recv_slice(&mut data), Err(Error::SocketClosed));
+        assert_eq!(socket.peek(1), Err(Error::SocketClosed));
+
+        // The socket was connected, but the connection is gone
+        let mut socket = connected_socket();
+        socket.set_state(State::Created);
+        assert!(socket.eof());
+        assert_eq!(socket.recv_slice(&mut data), Err(Error::SocketClosed));
+
+        // A socket connecting again is not closed, but not connected yet
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        assert_eq!(socket.transition(State::WaitingForConnect(remote)), Ok(()));
+        assert!(!socket.eof());
+        assert_eq!(socket.recv_slice(&mut data), Err(Error::Illegal));
+        assert_eq!(socket.peek(1), Err(Error::Illegal));
+
+        assert_eq!(socket.transition(State::Connected(remote)), Ok(()));
+        assert_eq!(socket.recv_slice(&mut data), Ok(0));
+
+        // A reset socket is not connected yet
+        socket.reset();
+        assert_eq!(socket.recv_slice(&mut data), Err(Error::Illegal));
+    }
+
+    #[test]
+    fn should_close_idle() {
+        let start = Instant::from_secs(100);
+        let mut socket = connected_socket();
+        socket.record_activity_at(start);
+        assert!(!socket.should_close_idle_at(start + Duration::from_secs(600)));
+
+        socket.set_idle_timeout(Some(Duration::from_secs(60)));
+        assert!(!socket.should_close_idle_at(start + Duration::from_secs(59)));
+        assert!(socket.should_close_idle_at(start + Duration::from_secs(60)));
+
+        socket.record_activity_at(start + Duration::from_secs(61));
+        assert!(!socket.should_close_idle_at(start + Duration::from_secs(62)));
+
+        // Received data counts as activity
+        socket.rx_enqueue_slice(b"hello");
+        assert!(!socket.should_close_idle_at(start + Duration::from_secs(130)));
+        assert!(!socket.should_close_idle_at(start + Duration::from_secs(189)));
+        assert!(socket.should_close_idle_at(start + Duration::from_secs(190)));
+
+        socket.closed_by_remote_at(start);
+        assert!(!socket.should_close_idle_at(start + Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn should_close_idle_after_connect() {
+        let start = Instant::from_secs(100);
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        let mut socket = TcpSocket::<64>::new(0);
+        socket.set_idle_timeout(Some(Duration::from_secs(60)));
+        socket.set_state_at(State::Connected(remote), start);
+
+        // Without any recorded activity, idleness is counted from connecting
+        assert!(!socket.should_close_idle_at(start + Duration::from_secs(59)));
+        assert!(socket.should_close_idle_at(start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    #[cfg(feature = "trace-history")]
+    fn state_history() {
+        let start = Instant::from_secs(100);
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        let mut socket = TcpSocket::<64>::new(0);
+        socket.set_state_at(State::Connected(remote), start);
+        socket.closed_by_remote_at(start + Duration::from_secs(5));
+        socket.reset_at(start + Duration::from_secs(10));
+
+        let history: heapless::Vec<(StateKind, Instant), STATE_HISTORY_LEN> =
+            socket.state_history().oldest_ordered().copied().collect();
+        assert_eq!(
+            history.as_slice(),
+            &[
+                (StateKind::Connected, start),
+                (StateKind::ShutdownForWrite, start + Duration::from_secs(5)),
+                (StateKind::Created, start + Duration::from_secs(10))
+            ]
+        );
+
+        for _ in 0..STATE_HISTORY_LEN {
+            socket.set_state_at(State::Created, start);
+        }
+        assert_eq!(socket.state_history().len(), STATE_HISTORY_LEN);
+    }
+
+    #[test]
+    fn security_profile() {
+        let mut socket = TcpSocket::<64>::new(0);
+        assert_eq!(socket.security_profile(), None);
+
+        assert_eq!(socket.set_security_profile(Some(5)), Err(Error::Illegal));
+        assert_eq!(socket.security_profile(), None);
+        assert_eq!(socket.set_security_profile(Some(4)), Ok(()));
+        assert_eq!(socket.security_profile(), Some(4));
+
+        socket.reset();
+        assert_eq!(socket.security_profile(), None);
+    }
+
+    #[test]
+    fn consume_available() {
+        let mut socket = connected_socket();
+        socket.mark_data_available(10);
+        socket.consume_available(4);
+        assert_eq!(socket.get_available_data(), 6);
+        assert!(!socket.should_update_available_data_at(Instant::from_secs(100)));
+
+        // Once all pushed data is consumed, polling resumes
+        socket.consume_available(8);
+        assert_eq!(socket.get_available_data(), 0);
+        assert!(socket.should_update_available_data_at(Instant::from_secs(100)));
+    }
+
+    #[test]
+    fn rx_running_crc() {
+        let mut socket = connected_socket();
+        assert_eq!(socket.rx_running_crc(), 0);
+
+        assert_eq!(socket.rx_enqueue_slice(b"12345"), 5);
+        assert_eq!(socket.recv_slice(&mut [0; 5]), Ok(5));
+        assert_eq!(socket.try_rx_enqueue_slice(b"6789"), Ok(()));
+        assert_eq!(socket.rx_running_crc(), 0xCBF4_3926);
+
+        socket.reset_rx_crc();
+        assert_eq!(socket.rx_running_crc(), 0);
+    }
+
+    #[test]
+    fn last_error_at() {
+        let failed = Instant::from_secs(100);
+        let mut socket = connected_socket();
+        assert_eq!(socket.last_error_at(), None);
+
+        socket.set_last_error_at(Error::Timeout, failed);
+        assert_eq!(socket.take_last_error(), Some(Error::Timeout));
+        assert_eq!(socket.last_error_at(), Some(failed));
+
+        socket.reset();
+        assert_eq!(socket.last_error_at(), None);
+    }
+
+    #[test]
+    fn close_reason() {
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        let mut socket = connected_socket();
+        assert_eq!(socket.close_reason(), None);
+
+        socket.closed_by_remote_at(Instant::from_secs(100));
+        socket.reset();
+        assert_eq!(socket.close_reason(), Some(CloseReason::RemoteReset));
+
+        // A new connection attempt clears the reason
+        socket.set_state(State::WaitingForConnect(remote));
+        assert_eq!(socket.close_reason(), None);
+
+        socket.set_close_reason(CloseReason::IdleTimeout);
+        assert_eq!(socket.close_reason(), Some(CloseReason::IdleTimeout));
+
+        // Moving back to Created does not record a reason by itself
+        let mut socket = connected_socket();
+        socket.set_state(State::Created);
+        assert_eq!(socket.close_reason(), None);
+    }
+
+    #[test]
+    fn transition() {
+        let remote = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 80));
+        let closed = Instant::from_secs(100);
+        let mut socket = TcpSocket::<64>::new(0);
+
+        assert_eq!(
+            socket.transition(State::ShutdownForWrite(closed)),
+            Err(Error::Illegal)
+        );
+        assert_eq!(
+            socket.transition(State::Connected(remote)),
+            Err(Error::Illegal)
+        );
+        assert_eq!(socket.state(), &State::Created);
+
+        assert_eq!(socket.transition(State::WaitingForConnect(remote)), Ok(()));
+        assert_eq!(socket.transition(State::Connected(remote)), Ok(()));
+        assert_eq!(
+            socket.transition(State::WaitingForConnect(remote)),
+            Err(Error::Illegal)
+        );
+        assert_eq!(socket.transition(State::ShutdownForWrite(closed)), Ok(()));
+        assert_eq!(socket.transition(State::Created), Ok(()));
+    }
+
+    #[test]
+    fn rx_cap() {
+        let mut socket = TcpSocket::<8>::new(0);
+        socket.set_rx_cap(16);
+        assert_eq!(socket.rx_cap(), 8);
+
+        socket.set_rx_cap(4);
+        assert_eq!(socket.rx_window(), 4);
+        assert_eq!(socket.rx_enqueue_slice(b"hello"), 4);
+        assert_eq!(socket.rx_window(), 0);
+        assert!(!socket.can_recv());
+        assert_eq!(socket.try_rx_enqueue_slice(b"a"), Err(Error::Exhausted));
+
+        // Lowering the cap below the amount already queued keeps the data
+        socket.set_rx_cap(2);
+        assert_eq!(socket.recv_queue(), 4);
+        assert_eq!(socket.rx_window(), 0);
+    }
+
+    #[test]
+    fn recv_deadline() {
+        let deadline = Instant::from_secs(100);
+        let mut socket = connected_socket();
+        let mut data = [0; 8];
+
+        assert_eq!(
+            socket.recv_deadline_at(&mut data, deadline, deadline - Duration::from_secs(1)),
+            Ok(0)
+        );
+        assert_eq!(
+            socket.recv_deadline_at(&mut data, deadline, deadline),
+            Err(Error::Timeout)
+        );
+
+        socket.rx_enqueue_slice(b"hello");
+        assert_eq!(
+            socket.recv_deadline_at(&mut data, deadline, deadline),
+            Ok(5)
+        );
+    }
+
+    #[test]
+    fn recycle() {
+        let closed = Instant::from_secs(100);
+        let mut socket = connected_socket();
+        assert!(!socket.recycle_at(closed));
+
+        socket.closed_by_remote_at(closed);
+        assert!(!socket.recycle_at(closed));
+        assert!(!socket.recycle_at(closed + Duration::from_secs(14)));
+        assert!(socket.recycle_at(closed + Duration::from_secs(15)));
+
+        socket.reset_timers_at(closed + Duration::from_secs(10));
+        assert!(!socket.recycle_at(closed + Duration::from_secs(15)));
+        assert!(socket.recycle_at(closed + Duration::from_secs(25)));
+    }
+}
@@ -1,7 +1,6 @@
 use heapless::{spsc::Queue, FnvIndexMap};
-use no_std_net::SocketAddr;
 
-use crate::SocketHandle;
+use crate::{SocketAddr, SocketHandle};
 
 pub struct TcpListener<const N: usize, const L: usize> {
     handles: FnvIndexMap<SocketHandle, u16, N>,
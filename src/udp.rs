@@ -1,8 +1,14 @@
 use core::cmp::min;
 
-use super::{Error, Result, RingBuffer, Socket, SocketHandle, SocketMeta};
+use super::{
+    Error, IpAddr, PollReason, Result, RingBuffer, Socket, SocketAddr, SocketHandle, SocketMeta,
+    SocketType, Watermarks, DEFAULT_CHECK_INTERVAL, DEFAULT_MAX_DATAGRAM_SIZE,
+    DEFAULT_READ_TIMEOUT, MAX_MULTICAST_GROUPS, MAX_SECURITY_PROFILE,
+};
+#[cfg(feature = "async")]
+use core::task::Waker;
 use embassy_time::{Duration, Instant};
-use no_std_net::SocketAddr;
+use heapless::Vec;
 
 /// A UDP socket ring buffer.
 pub type SocketBuffer<const N: usize> = RingBuffer<u8, N>;
@@ -19,33 +25,67 @@ pub enum State {
 /// A User Datagram Protocol socket.
 ///
 /// A UDP socket is bound to a specific endpoint, and owns transmit and receive
-/// packet buffers.
+/// packet buffers. It can be a member of up to [MAX_MULTICAST_GROUPS]
+/// multicast groups.
 #[derive(Debug)]
 pub struct UdpSocket<const L: usize> {
     pub(crate) meta: SocketMeta,
     pub(crate) endpoint: Option<SocketAddr>,
+    last_sender: Option<SocketAddr>,
     check_interval: Duration,
     read_timeout: Option<Duration>,
     state: State,
     available_data: usize,
+    available_data_pushed: bool,
     rx_buffer: SocketBuffer<L>,
+    watermarks: Watermarks,
+    rx_cap: usize,
+    max_datagram_size: usize,
+    token: u32,
+    last_error: Option<Error>,
+    last_error_at: Option<Instant>,
+    #[cfg(feature = "async")]
+    rx_waker: Option<Waker>,
+    ttl: Option<u8>,
+    dscp: Option<u8>,
+    security_profile: Option<u8>,
+    multicast_groups: Vec<IpAddr, MAX_MULTICAST_GROUPS>,
     last_check_time: Option<Instant>,
     closed_time: Option<Instant>,
 }
 
 impl<const L: usize> UdpSocket<L> {
     /// Create an UDP socket with the given buffers.
-    pub fn new(socket_id: u8) -> UdpSocket<L> {
+    ///
+    /// This is a `const fn`, and can be used to initialize a socket in a `static`.
+    pub const fn new(socket_id: u8) -> UdpSocket<L> {
         UdpSocket {
             meta: SocketMeta {
                 handle: SocketHandle(socket_id),
+                ty: SocketType::Udp,
+                pending_since: None,
+                generation: 0,
             },
-            check_interval: Duration::from_secs(15),
+            check_interval: DEFAULT_CHECK_INTERVAL,
             state: State::Closed,
-            read_timeout: Some(Duration::from_secs(15)),
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
             endpoint: None,
+            last_sender: None,
             available_data: 0,
-            rx_buffer: SocketBuffer::new(),
+            available_data_pushed: false,
+            rx_buffer: SocketBuffer::from_storage([0; L]),
+            watermarks: Watermarks::for_capacity(L),
+            rx_cap: L,
+            max_datagram_size: DEFAULT_MAX_DATAGRAM_SIZE,
+            token: 0,
+            last_error: None,
+            last_error_at: None,
+            #[cfg(feature = "async")]
+            rx_waker: None,
+            ttl: None,
+            dscp: None,
+            security_profile: None,
+            multicast_groups: Vec::new(),
             last_check_time: None,
             closed_time: None,
         }
@@ -65,6 +105,108 @@ impl<const L: usize> UdpSocket<L> {
         self.meta.update(handle)
     }
 
+    /// Return the user token associated with this socket.
+    pub fn token(&self) -> u32 {
+        self.token
+    }
+
+    /// Associate an application specific token with this socket, e.g. a
+    /// stream id, defaulting to `0`. It is cleared by [reset](#method.reset).
+    pub fn set_token(&mut self, token: u32) {
+        self.token = token;
+    }
+
+    /// Latch an error reported by the modem for this socket, e.g. a failed
+    /// write, so it can be surfaced to the application on its next poll.
+    ///
+    /// This replaces any error latched before. The latch is cleared by
+    /// [take_last_error](#method.take_last_error) and [reset](#method.reset).
+    pub fn set_last_error(&mut self, error: Error) {
+        self.set_last_error_at(error, Instant::now())
+    }
+
+    /// Same as [set_last_error](#method.set_last_error), but with `now` as the
+    /// time the error occurred.
+    pub fn set_last_error_at(&mut self, error: Error, now: Instant) {
+        debug!(
+            "[UDP Socket] [{:?}] Latching error {:?}",
+            self.handle(),
+            error
+        );
+        self.last_error = Some(error);
+        self.last_error_at = Some(now);
+    }
+
+    /// Return the time the last error was latched, if any, e.g. to hold off
+    /// reconnecting for a while after a failure.
+    ///
+    /// Unlike the error itself, it is kept by
+    /// [take_last_error](#method.take_last_error), and only cleared by
+    /// [reset](#method.reset).
+    pub fn last_error_at(&self) -> Option<Instant> {
+        self.last_error_at
+    }
+
+    /// Return and clear the latched error, if any.
+    pub fn take_last_error(&mut self) -> Option<Error> {
+        self.last_error.take()
+    }
+
+    /// Return whether an error is latched on this socket.
+    pub fn has_error(&self) -> bool {
+        self.last_error.is_some()
+    }
+
+    /// Return the time-to-live (or IPv6 hop limit) of outgoing packets, or
+    /// `None` to use the modem default.
+    pub fn ttl(&self) -> Option<u8> {
+        self.ttl
+    }
+
+    /// Set the time-to-live (or IPv6 hop limit) of outgoing packets, for the
+    /// driver to configure on the modem. It is cleared by [reset](#method.reset).
+    pub fn set_ttl(&mut self, ttl: Option<u8>) {
+        self.ttl = ttl;
+    }
+
+    /// Return the DSCP value of outgoing packets, or `None` to use the modem
+    /// default.
+    pub fn dscp(&self) -> Option<u8> {
+        self.dscp
+    }
+
+    /// Set the DSCP value of outgoing packets, for the driver to configure on
+    /// the modem. It is cleared by [reset](#method.reset).
+    ///
+    /// This function returns `Err(Error::Illegal)` if the value does not fit in
+    /// the 6 bit DSCP field, i.e. is greater than 63.
+    pub fn set_dscp(&mut self, dscp: Option<u8>) -> Result<()> {
+        if matches!(dscp, Some(dscp) if dscp > 63) {
+            return Err(Error::Illegal);
+        }
+        self.dscp = dscp;
+        Ok(())
+    }
+
+    /// Return the security profile (`+USECPRF`) of the socket, or `None` if
+    /// it is not secured.
+    pub fn security_profile(&self) -> Option<u8> {
+        self.security_profile
+    }
+
+    /// Set the security profile (`+USECPRF`) of the socket, for the driver to
+    /// configure on the modem. It is cleared by [reset](#method.reset).
+    ///
+    /// This function returns `Err(Error::Illegal)` if the profile id is not
+    /// supported by the modem, i.e. is greater than [MAX_SECURITY_PROFILE].
+    pub fn set_security_profile(&mut self, profile: Option<u8>) -> Result<()> {
+        if matches!(profile, Some(profile) if profile > MAX_SECURITY_PROFILE) {
+            return Err(Error::Illegal);
+        }
+        self.security_profile = profile;
+        Ok(())
+    }
+
     /// Return the bound endpoint.
     pub fn endpoint(&self) -> Option<SocketAddr> {
         self.endpoint
@@ -75,6 +217,25 @@ impl<const L: usize> UdpSocket<L> {
         self.state
     }
 
+    /// Return the socket to the state it was created in, closing it, and
+    /// discarding any received data.
+    pub fn reset(&mut self) {
+        self.set_state(State::default());
+        self.close();
+        self.rx_buffer.clear();
+        self.set_available_data(0);
+        self.last_check_time = None;
+        self.closed_time = None;
+        self.last_sender = None;
+        self.token = 0;
+        self.last_error = None;
+        self.last_error_at = None;
+        self.ttl = None;
+        self.dscp = None;
+        self.security_profile = None;
+        self.multicast_groups.clear();
+    }
+
     pub fn set_state(&mut self, state: State) {
         debug!(
             "[UDP Socket] {:?}, state change: {:?} -> {:?}",
@@ -85,18 +246,74 @@ impl<const L: usize> UdpSocket<L> {
         self.state = state
     }
 
+    /// Restart the available data check interval, and the recycle countdown
+    /// of a socket closed by the remote, without touching buffers or state.
+    pub fn reset_timers(&mut self) {
+        self.reset_timers_at(Instant::now())
+    }
+
+    /// Same as [reset_timers](#method.reset_timers), but with `now` as the
+    /// current time.
+    pub fn reset_timers_at(&mut self, now: Instant) {
+        self.last_check_time = None;
+        if let Some(closed_time) = self.closed_time.as_mut() {
+            *closed_time = now;
+        }
+    }
+
     pub fn should_update_available_data(&mut self) -> bool {
+        self.should_update_available_data_at(Instant::now())
+    }
+
+    /// Same as [should_update_available_data](#method.should_update_available_data),
+    /// but with `now` as the current time.
+    pub fn should_update_available_data_at(&mut self, now: Instant) -> bool {
+        // The modem already told us how much data is pending
+        if self.available_data_pushed {
+            return false;
+        }
+
         self.last_check_time
-            .replace(Instant::now())
-            .and_then(|last_check_time| Instant::now().checked_duration_since(last_check_time))
+            .replace(now)
+            .and_then(|last_check_time| now.checked_duration_since(last_check_time))
             .map(|dur| dur >= self.check_interval)
             .unwrap_or(false)
     }
 
+    /// Return the earliest time this socket needs attention, and why, with
+    /// `now` as the current time.
+    ///
+    /// A socket whose available data has never been checked is due at `now`.
+    pub fn next_deadline_at(&self, now: Instant) -> Option<(Instant, PollReason)> {
+        let data_poll = (!self.available_data_pushed).then(|| {
+            let at = self
+                .last_check_time
+                .map_or(now, |last_check_time| last_check_time + self.check_interval);
+            (at, PollReason::DataPoll)
+        });
+
+        let recycle = self
+            .read_timeout
+            .zip(self.closed_time)
+            .map(|(read_timeout, closed_time)| {
+                (closed_time + read_timeout, PollReason::RecycleDue)
+            });
+
+        [data_poll, recycle]
+            .into_iter()
+            .flatten()
+            .min_by_key(|&(at, _)| at)
+    }
+
     pub fn recycle(&self) -> bool {
+        self.recycle_at(Instant::now())
+    }
+
+    /// Same as [recycle](#method.recycle), but with `now` as the current time.
+    pub fn recycle_at(&self, now: Instant) -> bool {
         if let Some(read_timeout) = self.read_timeout {
             self.closed_time
-                .and_then(|closed_time| Instant::now().checked_duration_since(closed_time))
+                .and_then(|closed_time| now.checked_duration_since(closed_time))
                 .map(|dur| dur >= read_timeout)
                 .unwrap_or(false)
         } else {
@@ -105,12 +322,31 @@ impl<const L: usize> UdpSocket<L> {
     }
 
     pub fn closed_by_remote(&mut self) {
-        self.closed_time.replace(Instant::now());
+        self.closed_by_remote_at(Instant::now())
+    }
+
+    /// Same as [closed_by_remote](#method.closed_by_remote), but with `now` as
+    /// the time of closing.
+    pub fn closed_by_remote_at(&mut self, now: Instant) {
+        self.closed_time.replace(now);
     }
 
     /// Set available data.
     pub fn set_available_data(&mut self, available_data: usize) {
+        if available_data == 0 {
+            self.available_data_pushed = false;
+        }
+        self.available_data = available_data;
+    }
+
+    /// Set available data, as pushed by the modem through a URC.
+    ///
+    /// Until the pushed data has been consumed (available data is set back to
+    /// zero), [should_update_available_data](#method.should_update_available_data)
+    /// returns `false`, as polling the modem for the count would be redundant.
+    pub fn mark_data_available(&mut self, available_data: usize) {
         self.available_data = available_data;
+        self.available_data_pushed = available_data > 0;
     }
 
     /// Get the number of bytes available to ingress.
@@ -118,8 +354,69 @@ impl<const L: usize> UdpSocket<L> {
         self.available_data
     }
 
+    /// Subtract `n` octets read from the modem from the available data,
+    /// saturating at zero, so it keeps reflecting what is still left to read.
+    ///
+    /// The driver should call this with the amount actually enqueued, which
+    /// may be less than it requested if the receive buffer ran full.
+    pub fn consume_available(&mut self, n: usize) {
+        self.set_available_data(self.available_data.saturating_sub(n));
+    }
+
+    /// Return the amount of octets that can be enqueued into the receive
+    /// buffer, limited by the receive cap.
     pub fn rx_window(&self) -> usize {
-        self.rx_buffer.window()
+        min(
+            self.rx_buffer.window(),
+            self.rx_cap.saturating_sub(self.rx_buffer.len()),
+        )
+    }
+
+    /// Return the maximum amount of octets the receive buffer will hold.
+    pub fn rx_cap(&self) -> usize {
+        self.rx_cap
+    }
+
+    /// Limit the amount of octets the receive buffer will hold, similar to
+    /// `SO_RCVBUF`, to leave headroom for other sockets. Enqueueing beyond the
+    /// cap is rejected, even if there is space left in the receive buffer.
+    ///
+    /// The cap is clamped to the receive buffer capacity, which is the default.
+    pub fn set_rx_cap(&mut self, cap: usize) {
+        self.rx_cap = min(cap, L);
+    }
+
+    /// Return the receive buffer watermarks.
+    pub fn watermarks(&self) -> Watermarks {
+        self.watermarks
+    }
+
+    /// Set the receive buffer watermarks used by
+    /// [should_throttle](#method.should_throttle) and
+    /// [should_resume](#method.should_resume).
+    ///
+    /// Defaults to [Watermarks::for_capacity] of the receive buffer. This
+    /// function returns `Err(Error::Illegal)` if `low` is larger than `high`,
+    /// or `high` is larger than the receive buffer capacity.
+    pub fn set_watermarks(&mut self, watermarks: Watermarks) -> Result<()> {
+        if watermarks.low > watermarks.high || watermarks.high > L {
+            return Err(Error::Illegal);
+        }
+
+        self.watermarks = watermarks;
+        Ok(())
+    }
+
+    /// Return whether the receive buffer is filled above the high watermark,
+    /// and reading from the modem should be paused.
+    pub fn should_throttle(&self) -> bool {
+        self.rx_buffer.len() > self.watermarks.high
+    }
+
+    /// Return whether the receive buffer is drained below the low watermark,
+    /// and reading from the modem can be resumed.
+    pub fn should_resume(&self) -> bool {
+        self.rx_buffer.len() < self.watermarks.low
     }
 
     /// Bind the socket to the given endpoint.
@@ -141,9 +438,18 @@ impl<const L: usize> UdpSocket<L> {
         self.endpoint.is_some()
     }
 
+    /// Return whether the end of the stream has been reached, i.e. the socket
+    /// is not bound, and the receive buffer has been drained.
+    ///
+    /// A bound UDP socket may always receive more datagrams, so it is never at
+    /// the end of the stream.
+    pub fn eof(&self) -> bool {
+        !self.is_open() && self.rx_buffer.is_empty()
+    }
+
     /// Check whether the receive buffer is full.
     pub fn can_recv(&self) -> bool {
-        !self.rx_buffer.is_full()
+        self.rx_window() != 0
     }
 
     // /// Return the maximum number packets the socket can receive.
@@ -188,15 +494,137 @@ impl<const L: usize> UdpSocket<L> {
     /// and return the amount of octets copied as well as the endpoint.
     ///
     /// See also [recv](#method.recv).
+    ///
+    /// An empty `data` returns `Ok(0)` in any state, without side effects.
     pub fn recv_slice(&mut self, data: &mut [u8]) -> Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+
         self.recv_impl(|rx_buffer| {
             let size = rx_buffer.dequeue_slice(data);
             (size, size)
         })
     }
 
+    /// Dequeue received octets into `data` like [recv_slice](#method.recv_slice),
+    /// but return `Err(Error::Timeout)` if the receive buffer is empty and
+    /// `deadline` has passed.
+    pub fn recv_deadline(&mut self, data: &mut [u8], deadline: Instant) -> Result<usize> {
+        self.recv_deadline_at(data, deadline, Instant::now())
+    }
+
+    /// Same as [recv_deadline](#method.recv_deadline), but with `now` as the
+    /// current time.
+    pub fn recv_deadline_at(
+        &mut self,
+        data: &mut [u8],
+        deadline: Instant,
+        now: Instant,
+    ) -> Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        let size = self.recv_slice(data)?;
+        if size == 0 && self.rx_buffer.is_empty() && now >= deadline {
+            return Err(Error::Timeout);
+        }
+        Ok(size)
+    }
+
     pub fn rx_enqueue_slice(&mut self, data: &[u8]) -> usize {
-        self.rx_buffer.enqueue_slice(data)
+        let was_empty = self.rx_buffer.is_empty();
+        let len = min(data.len(), self.rx_window());
+        let len = self.rx_buffer.enqueue_slice(&data[..len]);
+        self.rx_enqueued(was_empty);
+        len
+    }
+
+    /// Enqueue a datagram received from `sender` into the receive buffer, and
+    /// return the amount of octets that could fit.
+    ///
+    /// The receive buffer does not preserve datagram boundaries, but `sender`
+    /// is recorded as the [last sender](#method.last_sender).
+    pub fn rx_enqueue_datagram(&mut self, data: &[u8], sender: SocketAddr) -> usize {
+        self.last_sender.replace(sender);
+        self.rx_enqueue_slice(data)
+    }
+
+    /// Return the sender of the last datagram enqueued with
+    /// [rx_enqueue_datagram](#method.rx_enqueue_datagram).
+    pub fn last_sender(&self) -> Option<SocketAddr> {
+        self.last_sender
+    }
+
+    /// Return whether the last datagram was received from another address than
+    /// the bound endpoint.
+    pub fn unexpected_source(&self) -> bool {
+        match (self.endpoint, self.last_sender) {
+            (Some(endpoint), Some(sender)) => endpoint != sender,
+            _ => false,
+        }
+    }
+
+    /// Enqueue all of `data` into the receive buffer.
+    ///
+    /// Unlike [rx_enqueue_slice](#method.rx_enqueue_slice), this never
+    /// truncates. If `data` does not fit in the receive buffer, nothing is
+    /// enqueued, and `Err(Error::Exhausted)` is returned.
+    pub fn try_rx_enqueue_slice(&mut self, data: &[u8]) -> Result<()> {
+        if data.len() > self.rx_window() {
+            return Err(Error::Exhausted);
+        }
+
+        let was_empty = self.rx_buffer.is_empty();
+        self.rx_buffer.enqueue_slice(data);
+        self.rx_enqueued(was_empty);
+        Ok(())
+    }
+
+    /// Update the receive buffer bookkeeping after enqueueing into it, given
+    /// whether it was empty before.
+    fn rx_enqueued(&mut self, was_empty: bool) {
+        #[cfg(feature = "async")]
+        if was_empty && !self.rx_buffer.is_empty() {
+            if let Some(waker) = self.rx_waker.take() {
+                waker.wake();
+            }
+        }
+        #[cfg(not(feature = "async"))]
+        let _ = was_empty;
+    }
+
+    /// Register `waker` to be woken when data is enqueued into the empty
+    /// receive buffer, e.g. by a future waiting to read from the socket.
+    ///
+    /// Only a single waker is stored, replacing any waker registered before.
+    /// It is woken at most once, and must be registered again afterwards.
+    #[cfg(feature = "async")]
+    pub fn register_rx_waker(&mut self, waker: &Waker) {
+        match &self.rx_waker {
+            Some(registered) if registered.will_wake(waker) => {}
+            _ => self.rx_waker = Some(waker.clone()),
+        }
+    }
+
+    /// Drain the receive buffer into `data`, and return the amount of octets
+    /// moved.
+    ///
+    /// Unlike [recv_slice](#method.recv_slice), this does not depend on the
+    /// socket state. If `data` is shorter than the amount of octets queued,
+    /// the remaining octets are left in the receive buffer.
+    pub fn take_rx(&mut self, data: &mut [u8]) -> usize {
+        self.rx_buffer.dequeue_slice(data)
+    }
+
+    pub(crate) fn rx_buffer(&self) -> &SocketBuffer<L> {
+        &self.rx_buffer
+    }
+
+    /// Return the amount of octets queued in the receive buffer.
+    pub fn recv_queue(&self) -> usize {
+        self.rx_buffer.len()
     }
 
     /// Peek at a packet received from a remote endpoint, and return the endpoint as well
@@ -204,7 +632,13 @@ impl<const L: usize> UdpSocket<L> {
     /// This function otherwise behaves identically to [recv](#method.recv).
     ///
     /// It returns `Err(Error::Exhausted)` if the receive buffer is empty.
+    ///
+    /// A `size` of zero returns an empty slice in any state.
     pub fn peek(&mut self, size: usize) -> Result<&[u8]> {
+        if size == 0 {
+            return Ok(&[]);
+        }
+
         if !self.is_open() {
             return Err(Error::Illegal);
         }
@@ -219,10 +653,73 @@ impl<const L: usize> UdpSocket<L> {
     ///
     /// See also [peek](#method.peek).
     pub fn peek_slice(&mut self, data: &mut [u8]) -> Result<usize> {
-        let buffer = self.peek(data.len())?;
-        let length = min(data.len(), buffer.len());
-        data[..length].copy_from_slice(&buffer[..length]);
-        Ok(length)
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        if !self.is_open() {
+            return Err(Error::Illegal);
+        }
+
+        Ok(self.rx_buffer.peek_into(data))
+    }
+
+    /// Join the multicast group `addr`.
+    ///
+    /// Joining a group the socket is already a member of has no effect. This
+    /// function returns `Err(Error::Exhausted)` if the socket is already a
+    /// member of [MAX_MULTICAST_GROUPS] groups.
+    pub fn join_multicast_group(&mut self, addr: IpAddr) -> Result<()> {
+        if self.multicast_groups.contains(&addr) {
+            return Ok(());
+        }
+
+        self.multicast_groups
+            .push(addr)
+            .map_err(|_| Error::Exhausted)
+    }
+
+    /// Leave the multicast group `addr`.
+    ///
+    /// This function returns `Err(Error::Illegal)` if the socket is not a
+    /// member of the group.
+    pub fn leave_multicast_group(&mut self, addr: IpAddr) -> Result<()> {
+        let index = self
+            .multicast_groups
+            .iter()
+            .position(|group| *group == addr)
+            .ok_or(Error::Illegal)?;
+        self.multicast_groups.swap_remove(index);
+        Ok(())
+    }
+
+    /// Return the multicast groups the socket is a member of.
+    pub fn multicast_groups(&self) -> &[IpAddr] {
+        &self.multicast_groups
+    }
+
+    /// Return the maximum size of a datagram sent through this socket.
+    pub fn max_datagram_size(&self) -> usize {
+        self.max_datagram_size
+    }
+
+    /// Set the maximum size of a datagram sent through this socket, defaulting
+    /// to [DEFAULT_MAX_DATAGRAM_SIZE], e.g. to match the per-write limit of
+    /// the modem.
+    pub fn set_max_datagram_size(&mut self, size: usize) {
+        self.max_datagram_size = size;
+    }
+
+    /// Check a datagram of `len` octets against the maximum datagram size,
+    /// before handing it to the modem.
+    ///
+    /// This function returns `Err(Error::BadLength)` if the datagram is too
+    /// large to be sent in a single write.
+    pub fn check_datagram_size(&self, len: usize) -> Result<()> {
+        if len > self.max_datagram_size {
+            return Err(Error::BadLength);
+        }
+        Ok(())
     }
 
     pub fn close(&mut self) {
@@ -242,3 +739,146 @@ impl<const L: usize> From<UdpSocket<L>> for Socket<L> {
         Socket::Udp(val)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use no_std_net::{Ipv4Addr, SocketAddrV4};
+
+    fn remote(port: u16) -> SocketAddr {
+        SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), port))
+    }
+
+    #[test]
+    fn unexpected_source() {
+        let mut socket = UdpSocket::<64>::new(0);
+        socket.bind(remote(1000)).unwrap();
+        assert_eq!(socket.last_sender(), None);
+        assert!(!socket.unexpected_source());
+
+        assert_eq!(socket.rx_enqueue_datagram(b"hello", remote(1000)), 5);
+        assert_eq!(socket.last_sender(), Some(remote(1000)));
+        assert!(!socket.unexpected_source());
+
+        assert_eq!(socket.rx_enqueue_datagram(b"world", remote(2000)), 5);
+        assert_eq!(socket.last_sender(), Some(remote(2000)));
+        assert!(socket.unexpected_source());
+    }
+
+    #[test]
+    fn token() {
+        let mut socket = UdpSocket::<64>::new(0);
+        assert_eq!(socket.token(), 0);
+
+        socket.set_token(7);
+        assert_eq!(socket.token(), 7);
+
+        socket.reset();
+        assert_eq!(socket.token(), 0);
+    }
+
+    #[test]
+    fn zero_length_recv() {
+        let mut socket = UdpSocket::<64>::new(0);
+        assert_eq!(socket.recv_slice(&mut []), Ok(0));
+        assert_eq!(socket.peek_slice(&mut []), Ok(0));
+
+        socket.bind(remote(1000)).unwrap();
+        assert_eq!(socket.rx_enqueue_slice(b"hello"), 5);
+        assert_eq!(socket.recv_slice(&mut []), Ok(0));
+        assert_eq!(socket.peek_slice(&mut []), Ok(0));
+        assert_eq!(socket.recv_queue(), 5);
+    }
+
+    #[test]
+    fn throttle_and_resume() {
+        let mut socket = UdpSocket::<64>::new(0);
+        assert!(socket.should_resume());
+        assert!(!socket.should_throttle());
+
+        // Above the default high watermark of 48 octets
+        assert_eq!(socket.rx_enqueue_slice(&[0; 49]), 49);
+        assert!(socket.should_throttle());
+        assert!(!socket.should_resume());
+
+        // Between the watermarks, neither signal is raised
+        assert_eq!(socket.take_rx(&mut [0; 17]), 17);
+        assert!(!socket.should_throttle());
+        assert!(!socket.should_resume());
+
+        assert_eq!(socket.take_rx(&mut [0; 17]), 17);
+        assert!(socket.should_resume());
+    }
+
+    #[test]
+    fn multicast_groups() {
+        let group = |last| IpAddr::V4(Ipv4Addr::new(239, 0, 0, last));
+        let mut socket = UdpSocket::<64>::new(0);
+        assert_eq!(socket.multicast_groups(), &[]);
+
+        assert_eq!(socket.join_multicast_group(group(1)), Ok(()));
+        // Joining a group twice has no effect
+        assert_eq!(socket.join_multicast_group(group(1)), Ok(()));
+        assert_eq!(socket.multicast_groups(), &[group(1)]);
+
+        for last in 2..=MAX_MULTICAST_GROUPS as u8 {
+            assert_eq!(socket.join_multicast_group(group(last)), Ok(()));
+        }
+        assert_eq!(
+            socket.join_multicast_group(group(100)),
+            Err(Error::Exhausted)
+        );
+        assert_eq!(socket.multicast_groups().len(), MAX_MULTICAST_GROUPS);
+
+        assert_eq!(
+            socket.leave_multicast_group(group(100)),
+            Err(Error::Illegal)
+        );
+        assert_eq!(socket.leave_multicast_group(group(1)), Ok(()));
+        assert!(!socket.multicast_groups().contains(&group(1)));
+
+        socket.reset();
+        assert_eq!(socket.multicast_groups(), &[]);
+    }
+
+    #[test]
+    fn max_datagram_size() {
+        let mut socket = UdpSocket::<64>::new(0);
+        assert_eq!(socket.max_datagram_size(), DEFAULT_MAX_DATAGRAM_SIZE);
+        assert_eq!(socket.check_datagram_size(1024), Ok(()));
+        assert_eq!(socket.check_datagram_size(1025), Err(Error::BadLength));
+
+        socket.set_max_datagram_size(512);
+        assert_eq!(socket.check_datagram_size(512), Ok(()));
+        assert_eq!(socket.check_datagram_size(513), Err(Error::BadLength));
+    }
+
+    #[test]
+    fn ttl_and_dscp() {
+        let mut socket = UdpSocket::<64>::new(0);
+        assert_eq!(socket.ttl(), None);
+        assert_eq!(socket.dscp(), None);
+
+        socket.set_ttl(Some(32));
+        assert_eq!(socket.set_dscp(Some(64)), Err(Error::Illegal));
+        assert_eq!(socket.dscp(), None);
+        assert_eq!(socket.set_dscp(Some(46)), Ok(()));
+        assert_eq!(socket.ttl(), Some(32));
+        assert_eq!(socket.dscp(), Some(46));
+
+        socket.reset();
+        assert_eq!(socket.ttl(), None);
+        assert_eq!(socket.dscp(), None);
+    }
+
+    #[test]
+    fn security_profile() {
+        let mut socket = UdpSocket::<64>::new(0);
+        assert_eq!(socket.set_security_profile(Some(5)), Err(Error::Illegal));
+        assert_eq!(socket.set_security_profile(Some(0)), Ok(()));
+        assert_eq!(socket.security_profile(), Some(0));
+
+        socket.reset();
+        assert_eq!(socket.security_profile(), None);
+    }
+}
@@ -1,8 +1,14 @@
 use heapless::{spsc::Queue, FnvIndexMap};
-use no_std_net::SocketAddr;
 
-use crate::{Error, SocketHandle};
+use crate::{Error, Result, SocketAddr, SocketHandle};
+#[cfg(feature = "socket-udp")]
+use crate::{SocketSet, UdpSocket, UdpState};
 
+/// A set of UDP server sockets, each with a queue of incomming connections.
+///
+/// `N` is the maximum number of bound server sockets, and must be a power of
+/// two. `L` is the size of each incomming connection queue, which holds at
+/// most `L - 1` connections.
 pub struct UdpListener<const N: usize, const L: usize> {
     /// Maps Server Socket handles to ports
     handles: FnvIndexMap<SocketHandle, u16, N>,
@@ -24,29 +30,56 @@ impl<const N: usize, const L: usize> UdpListener<N, L> {
         }
     }
 
+    /// Get the number of server sockets currently bound
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns true if no server sockets are bound
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Returns true if no more server sockets can be bound
+    pub fn is_full(&self) -> bool {
+        self.handles.len() == self.handles.capacity()
+    }
+
+    /// Returns whether the incomming connection queue for port is full, or
+    /// `None` if port is not a UDP server port
+    pub fn is_queue_full(&self, port: u16) -> Option<bool> {
+        self.connections.get(&port).map(|queue| queue.is_full())
+    }
+
     /// Bind sockethandle to port, and create queue for incomming sockets
-    pub fn bind(&mut self, handle: SocketHandle, port: u16) -> Result<(), Error> {
+    ///
+    /// Returns `Err(Error::DuplicateSocket)` if the handle is already bound, and
+    /// `Err(Error::SocketSetFull)` if no more server sockets can be bound.
+    pub fn bind(&mut self, handle: SocketHandle, port: u16) -> Result<()> {
         if self.handles.contains_key(&handle) {
-            return Err(Error::ListenerError);
+            return Err(Error::DuplicateSocket);
         }
 
         self.handles
             .insert(handle, port)
-            .map_err(|_| Error::ListenerError)?;
-        self.connections
-            .insert(port, Queue::new())
-            .map_err(|_| Error::ListenerError)?;
+            .map_err(|_| Error::SocketSetFull)?;
+
+        // Roll back the handle, to never leave it bound to a port without a queue
+        if self.connections.insert(port, Queue::new()).is_err() {
+            self.handles.remove(&handle);
+            return Err(Error::SocketSetFull);
+        }
 
         Ok(())
     }
 
     /// Unbind sockethandle to port, and create queue for incomming sockets
-    pub fn unbind(&mut self, handle: SocketHandle) -> Result<(), Error> {
+    pub fn unbind(&mut self, handle: SocketHandle) -> Result<()> {
         if let Some(port) = self.handles.remove(&handle) {
             self.connections.remove(&port);
             Ok(())
         } else {
-            Err(Error::ListenerError)
+            Err(Error::NotBound)
         }
     }
 
@@ -55,6 +88,16 @@ impl<const N: usize, const L: usize> UdpListener<N, L> {
         self.connections.get_mut(&port)
     }
 
+    /// Iterate every queued incomming connection across all ports, as
+    /// `(port, connection handle, remote address)`, without removing them.
+    pub fn all_pending(&self) -> impl Iterator<Item = (u16, SocketHandle, &SocketAddr)> {
+        self.connections.iter().flat_map(|(port, queue)| {
+            queue
+                .iter()
+                .map(move |(handle, remote)| (*port, *handle, remote))
+        })
+    }
+
     /// Returns true if port is UDP server port
     pub fn is_port_bound(&self, port: u16) -> bool {
         self.connections.get(&port).is_some()
@@ -66,43 +109,75 @@ impl<const N: usize, const L: usize> UdpListener<N, L> {
     }
 
     /// See if a connection is available for server
-    pub fn available(&mut self, handle: SocketHandle) -> Result<bool, Error> {
-        let port = self.handles.get(&handle).ok_or(Error::ListenerError)?;
+    pub fn available(&mut self, handle: SocketHandle) -> Result<bool> {
+        let port = self.handles.get(&handle).ok_or(Error::NotBound)?;
         Ok(!self
             .connections
             .get_mut(port)
-            .ok_or(Error::ListenerError)?
+            .ok_or(Error::NotBound)?
             .is_empty())
     }
 
     /// Peek from queue of incomming connections for socket.
-    pub fn peek_remote(
-        &mut self,
-        handle: SocketHandle,
-    ) -> Result<&(SocketHandle, SocketAddr), Error> {
-        let port = self.handles.get(&handle).ok_or(Error::ListenerError)?;
+    ///
+    /// Returns `Err(Error::Exhausted)` if the queue is empty.
+    pub fn peek_remote(&mut self, handle: SocketHandle) -> Result<&(SocketHandle, SocketAddr)> {
+        let port = self.handles.get(&handle).ok_or(Error::NotBound)?;
         self.connections
             .get_mut(port)
-            .ok_or(Error::ListenerError)?
+            .ok_or(Error::NotBound)?
             .peek()
-            .ok_or(Error::ListenerError)
+            .ok_or(Error::Exhausted)
     }
 
     /// Pop from queue of incomming connections for socket.
-    pub fn get_remote(
-        &mut self,
-        handle: SocketHandle,
-    ) -> Result<(SocketHandle, SocketAddr), Error> {
-        let port = self.handles.get(&handle).ok_or(Error::ListenerError)?;
+    ///
+    /// Returns `Err(Error::Exhausted)` if the queue is empty.
+    pub fn get_remote(&mut self, handle: SocketHandle) -> Result<(SocketHandle, SocketAddr)> {
+        let port = self.handles.get(&handle).ok_or(Error::NotBound)?;
         self.connections
             .get_mut(port)
-            .ok_or(Error::ListenerError)?
+            .ok_or(Error::NotBound)?
             .dequeue()
-            .ok_or(Error::ListenerError)
+            .ok_or(Error::Exhausted)
     }
 
-    pub fn get_port(&mut self, handle: SocketHandle) -> Result<u16, Error> {
-        let port = self.handles.get(&handle).ok_or(Error::ListenerError)?;
+    /// Pop the next incomming connection for server socket, and make sure the
+    /// connection socket exists in `set`, established with the remote address
+    /// as its endpoint.
+    ///
+    /// A UDP socket is added to `set` if the connection handle is unknown. The
+    /// connection is left queued if this fails, e.g. with
+    /// `Err(Error::SocketSetFull)`, or `Err(Error::Illegal)` if the handle
+    /// belongs to a socket of another type.
+    #[cfg(feature = "socket-udp")]
+    pub fn accept_into<const SN: usize, const SL: usize>(
+        &mut self,
+        server: SocketHandle,
+        set: &mut SocketSet<SN, SL>,
+    ) -> Result<SocketHandle> {
+        let (handle, remote) = *self.peek_remote(server)?;
+
+        match set.get_udp(handle) {
+            Ok(mut socket) => {
+                socket.endpoint = Some(remote);
+                socket.set_state(UdpState::Established);
+            }
+            Err(Error::InvalidSocket) => {
+                let mut socket = UdpSocket::new(handle.0);
+                socket.bind(remote)?;
+                socket.set_state(UdpState::Established);
+                set.add(socket)?;
+            }
+            Err(e) => return Err(e),
+        }
+
+        self.get_remote(server)?;
+        Ok(handle)
+    }
+
+    pub fn get_port(&mut self, handle: SocketHandle) -> Result<u16> {
+        let port = self.handles.get(&handle).ok_or(Error::NotBound)?;
         Ok(*port)
     }
 
@@ -140,7 +215,163 @@ impl core::hash::Hash for SocketAddrWrapper {
             SocketAddr::V6(ip) => {
                 ip.ip().octets().hash(state);
                 ip.port().hash(state);
+                // Link-local peers on different interfaces share an address
+                ip.scope_id().hash(state);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use no_std_net::{Ipv4Addr, SocketAddrV4};
+
+    fn remote(port: u16) -> SocketAddr {
+        SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), port))
+    }
+
+    #[test]
+    fn bind_full_listener() {
+        let mut listener = UdpListener::<2, 4>::new();
+
+        assert_eq!(listener.bind(SocketHandle(0), 1000), Ok(()));
+        assert_eq!(listener.bind(SocketHandle(1), 1001), Ok(()));
+        assert!(listener.is_full());
+
+        assert_eq!(
+            listener.bind(SocketHandle(2), 1002),
+            Err(Error::SocketSetFull)
+        );
+        assert_eq!(listener.len(), 2);
+        assert!(!listener.is_bound(SocketHandle(2)));
+        assert!(!listener.is_port_bound(1002));
+    }
+
+    #[test]
+    fn bind_duplicate_handle() {
+        let mut listener = UdpListener::<2, 4>::new();
+
+        assert_eq!(listener.bind(SocketHandle(0), 1000), Ok(()));
+        assert_eq!(
+            listener.bind(SocketHandle(0), 1001),
+            Err(Error::DuplicateSocket)
+        );
+        assert_eq!(listener.len(), 1);
+        assert_eq!(listener.get_port(SocketHandle(0)), Ok(1000));
+        assert!(!listener.is_port_bound(1001));
+    }
+
+    #[test]
+    fn unbind() {
+        let mut listener = UdpListener::<2, 4>::new();
+
+        assert_eq!(listener.bind(SocketHandle(0), 1000), Ok(()));
+        assert_eq!(listener.unbind(SocketHandle(0)), Ok(()));
+        assert!(listener.is_empty());
+        assert!(!listener.is_port_bound(1000));
+        assert_eq!(listener.unbind(SocketHandle(0)), Err(Error::NotBound));
+    }
+
+    #[test]
+    fn all_pending() {
+        let mut listener = UdpListener::<2, 4>::new();
+
+        assert_eq!(listener.bind(SocketHandle(0), 1000), Ok(()));
+        assert_eq!(listener.bind(SocketHandle(1), 1001), Ok(()));
+        assert_eq!(listener.all_pending().count(), 0);
+
+        let queue = listener.incoming(1000).unwrap();
+        queue.enqueue((SocketHandle(2), remote(2000))).unwrap();
+        queue.enqueue((SocketHandle(3), remote(3000))).unwrap();
+        let queue = listener.incoming(1001).unwrap();
+        queue.enqueue((SocketHandle(4), remote(4000))).unwrap();
+
+        assert!(listener.all_pending().eq([
+            (1000, SocketHandle(2), &remote(2000)),
+            (1000, SocketHandle(3), &remote(3000)),
+            (1001, SocketHandle(4), &remote(4000)),
+        ]));
+
+        // Iterating does not consume the queues
+        assert_eq!(listener.all_pending().count(), 3);
+    }
+
+    #[test]
+    fn accept_into() {
+        let mut listener = UdpListener::<2, 4>::new();
+        let mut set = SocketSet::<2, 64>::new();
+        assert_eq!(listener.bind(SocketHandle(0), 1000), Ok(()));
+        assert_eq!(
+            listener.accept_into(SocketHandle(0), &mut set),
+            Err(Error::Exhausted)
+        );
+
+        let queue = listener.incoming(1000).unwrap();
+        queue.enqueue((SocketHandle(1), remote(2000))).unwrap();
+        queue.enqueue((SocketHandle(2), remote(3000))).unwrap();
+        queue.enqueue((SocketHandle(3), remote(4000))).unwrap();
+
+        // A new socket is added for an unknown handle
+        assert_eq!(
+            listener.accept_into(SocketHandle(0), &mut set),
+            Ok(SocketHandle(1))
+        );
+        let socket = set.get_udp(SocketHandle(1)).unwrap();
+        assert_eq!(socket.endpoint(), Some(remote(2000)));
+        assert_eq!(socket.state(), UdpState::Established);
+
+        // An existing socket is reused
+        assert_eq!(set.add(UdpSocket::new(2)), Ok(SocketHandle(2)));
+        assert_eq!(
+            listener.accept_into(SocketHandle(0), &mut set),
+            Ok(SocketHandle(2))
+        );
+        assert_eq!(
+            set.get_udp(SocketHandle(2)).unwrap().endpoint(),
+            Some(remote(3000))
+        );
+
+        // The connection stays queued if the set is full
+        assert_eq!(
+            listener.accept_into(SocketHandle(0), &mut set),
+            Err(Error::SocketSetFull)
+        );
+        assert_eq!(listener.all_pending().count(), 1);
+    }
+
+    #[test]
+    fn link_local_scope() {
+        use core::hash::{Hash, Hasher};
+        use no_std_net::{Ipv6Addr, SocketAddrV6};
+        use std::collections::hash_map::DefaultHasher;
+
+        let link_local = |scope_id| {
+            SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+                2000,
+                0,
+                scope_id,
+            ))
+        };
+        let hash = |addr| {
+            let mut hasher = DefaultHasher::new();
+            SocketAddrWrapper(addr).hash(&mut hasher);
+            hasher.finish()
+        };
+
+        assert!(SocketAddrWrapper(link_local(1)) != SocketAddrWrapper(link_local(2)));
+        assert_ne!(hash(link_local(1)), hash(link_local(2)));
+
+        // Queued connections keep the scope id of their peer
+        let mut listener = UdpListener::<2, 4>::new();
+        assert_eq!(listener.bind(SocketHandle(0), 1000), Ok(()));
+        let queue = listener.incoming(1000).unwrap();
+        queue.enqueue((SocketHandle(1), link_local(1))).unwrap();
+        assert_eq!(listener.get_outgoing(&SocketHandle(0), link_local(2)), None);
+        assert_eq!(
+            listener.get_outgoing(&SocketHandle(0), link_local(1)),
+            Some(SocketHandle(1))
+        );
+    }
+}